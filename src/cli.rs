@@ -1,4 +1,7 @@
-use clap::Parser;
+use clap::builder::RangedU64ValueParser;
+use clap::{Parser, Subcommand};
+
+use crate::output::Format;
 
 #[derive(Parser, Debug)]
 pub struct Cli {
@@ -6,17 +9,132 @@ pub struct Cli {
     #[arg(value_name = "PATH")]
     pub path: Option<String>,
 
-    /// GROUP BY clause - field to group files by (e.g., 'extension', 'size', etc.)
+    /// GROUP BY clause - field to group files by (e.g., 'extension', 'size', etc.); may be repeated
+    /// to group by several keys, outermost first
+    /// Example: -g extension -g modified,year,month
     #[arg(short, long, value_name = "FIELD")]
-    pub group_by: Option<String>,
+    pub group_by: Vec<String>,
 
-    /// WHERE clause - filter condition in format: field,operator,value
-    /// Examples: size,gt,123 or name,test_*
-    #[arg(short, long, value_name = "CONDITION")]
+    /// WHERE clause - filter condition in format: field,operator,value or field operator value,
+    /// optionally combined with AND, OR, NOT and parentheses
+    /// Sizes accept units: 10K, 1.5MiB, 2GB
+    /// Examples: size,gt,123 or name,test_* or "size > 1000 AND (ext = log OR ext = tmp)"
+    #[arg(short, long, value_name = "CONDITION", global = true)]
     pub r#where: Option<String>,
 
-    /// Aggregating function to use
-    /// Examples: count or sum or max,size or min,mod or avg
+    /// HAVING clause - condition on aggregate values that groups must satisfy, in the same syntax as --where
+    /// Aggregates are written as count, avg, sum(size), max(modified) or by their output key (sum_size)
+    /// Examples: "count > 100" or "sum(size) >= 1000000 AND NOT count < 5"
+    #[arg(long, value_name = "CONDITION")]
+    pub having: Option<String>,
+
+    /// Aggregating function to use; may be repeated to compute several aggregates per group
+    /// Examples: count or sum or max,size or min,mod or avg,age or median or p95,size or stddev,modified or mode
+    /// or histogram,size,log2 or histogram,lines,linear,100 or histogram,modified,year,month
     #[arg(short, long, value_name = "FUNCTION")]
-    pub aggregate: Option<String>,
-}
\ No newline at end of file
+    pub aggregate: Vec<String>,
+
+    /// SELECT clause - comma-separated columns to show when listing files, in order
+    /// Available: name, path, depth, extension, size, modified, accessed, created, file_type,
+    /// link_target, broken_link, owner, owner_group, uid, gid, mode, permissions, setuid, setgid, sticky,
+    /// inode, device, nlink, disk_usage, hash, sha256, mime, kind, lines, words, chars, is_binary, is_utf8,
+    /// matches
+    /// Example: name,size,modified
+    #[arg(short, long, value_name = "COLUMNS")]
+    pub select: Option<String>,
+
+    /// ORDER BY clause - sort key in format: field[,asc|desc] or aggregate[,asc|desc]; may be repeated
+    /// Field keys sort files within each group (and groups by key when naming the grouped field),
+    /// aggregate keys sort groups by their aggregate value
+    /// Examples: size,desc or name or count,desc or sum,size,desc
+    #[arg(short, long, value_name = "KEY")]
+    pub order_by: Vec<String>,
+
+    /// Maximum number of rows to show: files per group when listing, groups when aggregating
+    #[arg(short, long, value_name = "N", global = true)]
+    pub limit: Option<usize>,
+
+    /// Number of rows to skip before applying --limit
+    #[arg(long, value_name = "N", default_value_t = 0, global = true)]
+    pub offset: usize,
+
+    /// Render aggregates over several group keys as a hierarchy with a subtotal row per key prefix
+    #[arg(long, global = true)]
+    pub nested: bool,
+
+    /// Output format; structured formats share one schema with a `group` column,
+    /// typed values and ISO 8601 timestamps
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = Format::Table, global = true)]
+    pub format: Format,
+
+    /// Show sizes in text tables with binary units such as KiB and MiB instead of bytes
+    #[arg(long, global = true)]
+    pub human: bool,
+
+    /// Time zone for date literals, time grouping and displayed timestamps:
+    /// local (default), UTC, a fixed offset such as +02:00, or an IANA name such as Europe/Warsaw
+    #[arg(long, value_name = "ZONE", global = true)]
+    pub tz: Option<String>,
+
+    /// Format of timestamps in text tables: a strftime format such as "%d.%m.%Y %H:%M",
+    /// or iso, rfc2822 or unix
+    #[arg(long, value_name = "FORMAT", global = true)]
+    pub date_format: Option<String>,
+
+    /// Descend into subdirectories instead of listing a single level
+    #[arg(short, long, global = true)]
+    pub recursive: bool,
+
+    /// Maximum depth to descend to; direct children of PATH have depth 1 (implies --recursive)
+    #[arg(long, value_name = "N", global = true, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub max_depth: Option<usize>,
+
+    /// Minimum depth of entries to include; shallower entries are still traversed (implies --recursive)
+    #[arg(long, value_name = "N", global = true, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub min_depth: Option<usize>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Run a SQL-like query instead of using the flags above
+    /// Example: lsdir query "SELECT extension, count(*) FROM . GROUP BY extension ORDER BY count(*) DESC"
+    Query {
+        /// SELECT <columns | aggregate> [FROM <path>] [WHERE <condition>] [GROUP BY <field>[(<args>)], ...]
+        /// [HAVING <aggregate condition>]
+        /// [ORDER BY <column | aggregate> [ASC|DESC], ...] [LIMIT <n> [OFFSET <m>]]
+        #[arg(value_name = "SQL")]
+        sql: String,
+    },
+    /// Find sets of files with identical contents and the space they waste
+    /// Compares sizes first, then the first bytes, and only then whole files;
+    /// --where, --limit, --offset and the walk options apply
+    /// Example: lsdir -r duplicates ~/assets --where "size > 1000000"
+    Duplicates {
+        /// Directory path to analyze (defaults to current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<String>,
+
+        /// Compare contents by SHA-256 instead of the faster non-cryptographic XXH3 hash
+        #[arg(long)]
+        sha256: bool,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicates_accepts_filter_flags() {
+        let args = Cli::try_parse_from(["lsdir", "-r", "duplicates", "~/assets", "--where", "size > 1000000"]).unwrap();
+        assert!(args.recursive);
+        assert_eq!(args.r#where.as_deref(), Some("size > 1000000"));
+        assert!(matches!(args.command, Some(Command::Duplicates { path: Some(ref path), .. }) if path == "~/assets"));
+
+        let args = Cli::try_parse_from(["lsdir", "duplicates", "--limit", "5", "--offset", "10"]).unwrap();
+        assert_eq!((args.limit, args.offset), (Some(5), 10));
+    }
+}
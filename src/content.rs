@@ -0,0 +1,633 @@
+//! Lazily computed attributes derived from file contents.
+//!
+//! Reading contents is expensive compared to reading metadata, so these
+//! attributes are computed on first use and cached in the `File` they belong
+//! to. Queries that never refer to them never open a file.
+
+use std::fs;
+use std::io::{self, BufRead, BufReader, Cursor, Read};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use regex::bytes::Regex;
+use sha2::{Digest, Sha256};
+use xxhash_rust::xxh3::Xxh3;
+
+/// The number of bytes hashed by `duplicates` to cheaply rule out files of equal size.
+pub const PARTIAL_HASH_BYTES: u64 = 4096;
+
+/// The number of leading bytes inspected to determine the MIME type of a file.
+pub const SNIFF_BYTES: u64 = 8192;
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// The byte order marks of the Unicode encodings recognized as text, with their charsets.
+const BYTE_ORDER_MARKS: [(&[u8], &str); 3] = [
+    (&[0xEF, 0xBB, 0xBF], "utf-8"),
+    (&[0xFF, 0xFE], "utf-16le"),
+    (&[0xFE, 0xFF], "utf-16be"),
+];
+
+/// The hash functions available for content hashes.
+///
+/// # Variants
+/// - `Xxh3`: The fast non-cryptographic 128-bit XXH3 hash
+/// - `Sha256`: The cryptographic SHA-256 hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Xxh3,
+    Sha256,
+}
+
+/// Computes the hash of the first `limit` bytes of a file (all of it when
+/// `limit` is `None`), as a lowercase hexadecimal string.
+///
+/// The file is read in chunks, so memory use does not depend on its size.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be opened or read.
+pub fn hash_file(path: &Path, algorithm: HashAlgorithm, limit: Option<u64>) -> io::Result<String> {
+    let file = fs::File::open(path)?;
+    let mut reader = file.take(limit.unwrap_or(u64::MAX));
+    let mut buffer = vec![0; BUFFER_SIZE];
+
+    match algorithm {
+        HashAlgorithm::Xxh3 => {
+            let mut hasher = Xxh3::new();
+            loop {
+                let read = reader.read(&mut buffer)?;
+                if read == 0 {
+                    return Ok(format!("{:032x}", hasher.digest128()));
+                }
+                hasher.update(&buffer[..read]);
+            }
+        }
+        HashAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            loop {
+                let read = reader.read(&mut buffer)?;
+                if read == 0 {
+                    return Ok(hasher
+                        .finalize()
+                        .iter()
+                        .map(|byte| format!("{:02x}", byte))
+                        .collect());
+                }
+                hasher.update(&buffer[..read]);
+            }
+        }
+    }
+}
+
+/// Reads the first `SNIFF_BYTES` bytes of a file and determines its MIME type.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be opened or read.
+pub fn sniff_file(path: &Path) -> io::Result<String> {
+    let mut bytes = Vec::new();
+    fs::File::open(path)?.take(SNIFF_BYTES).read_to_end(&mut bytes)?;
+    Ok(sniff_mime(&bytes, (bytes.len() as u64) < SNIFF_BYTES))
+}
+
+/// Determines a MIME type from the leading bytes of a file's contents.
+///
+/// Well-known binary formats are recognized by their magic bytes. Everything
+/// else is classified as text, with a `charset` parameter naming its
+/// encoding, or as `application/octet-stream`. Empty contents are reported as
+/// `inode/x-empty`, like `file --mime-type` does.
+///
+/// # Arguments
+///
+/// * `bytes` - The leading bytes of the contents
+/// * `complete` - Whether `bytes` hold the whole contents, so that a
+///   multi-byte character cut off at the end is not taken as invalid text
+pub fn sniff_mime(bytes: &[u8], complete: bool) -> String {
+    if bytes.is_empty() {
+        return "inode/x-empty".to_string();
+    }
+    let bom = BYTE_ORDER_MARKS.into_iter().find(|(mark, _)| bytes.starts_with(mark));
+    // A byte order mark takes precedence, as it could be mistaken for an MPEG frame header
+    if bom.is_none()
+        && let Some(mime) = sniff_magic(bytes)
+    {
+        return mime.to_string();
+    }
+
+    let charset = if let Some((_, charset)) = bom {
+        charset
+    } else if is_binary(bytes) {
+        return "application/octet-stream".to_string();
+    } else if bytes.is_ascii() {
+        "us-ascii"
+    } else {
+        match std::str::from_utf8(bytes) {
+            Ok(_) => "utf-8",
+            // A character cut off by the end of the sample
+            Err(e) if !complete && e.error_len().is_none() => "utf-8",
+            Err(_) => return "application/octet-stream".to_string(),
+        }
+    };
+
+    let text = String::from_utf8_lossy(&bytes[..bytes.len().min(512)]);
+    let head = text.trim_start_matches('\u{feff}').trim_start().to_lowercase();
+    let subtype = if head.starts_with("#!") {
+        let interpreter = head.lines().next().unwrap_or_default();
+        if interpreter.contains("python") {
+            "x-script.python"
+        } else if ["sh", "bash", "zsh", "dash", "ksh"]
+            .iter()
+            .any(|shell| interpreter.ends_with(&format!("/{}", shell)) || interpreter.ends_with(&format!(" {}", shell)))
+        {
+            "x-shellscript"
+        } else {
+            "x-script"
+        }
+    } else if head.starts_with("<!doctype html") || head.starts_with("<html") {
+        "html"
+    } else if head.starts_with("<svg") || (head.starts_with("<?xml") && head.contains("<svg")) {
+        return "image/svg+xml".to_string();
+    } else if head.starts_with("<?xml") {
+        "xml"
+    } else if head.starts_with("%!ps") {
+        return "application/postscript".to_string();
+    } else {
+        "plain"
+    };
+    format!("text/{}; charset={}", subtype, charset)
+}
+
+/// Recognizes binary formats by their magic bytes.
+fn sniff_magic(bytes: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: [(&[u8], &str); 27] = [
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"II*\x00", "image/tiff"),
+        (b"MM\x00*", "image/tiff"),
+        (b"\x00\x00\x01\x00", "image/vnd.microsoft.icon"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"PK\x05\x06", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"BZh", "application/x-bzip2"),
+        (b"\xfd7zXZ\x00", "application/x-xz"),
+        (b"\x28\xb5\x2f\xfd", "application/zstd"),
+        (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+        (b"Rar!\x1a\x07", "application/vnd.rar"),
+        (b"SQLite format 3\x00", "application/vnd.sqlite3"),
+        (b"\x00asm", "application/wasm"),
+        (b"\xca\xfe\xba\xbe", "application/java-vm"),
+        (b"\xfe\xed\xfa\xce", "application/x-mach-binary"),
+        (b"\xfe\xed\xfa\xcf", "application/x-mach-binary"),
+        (b"\xce\xfa\xed\xfe", "application/x-mach-binary"),
+        (b"\xcf\xfa\xed\xfe", "application/x-mach-binary"),
+        (b"MZ", "application/vnd.microsoft.portable-executable"),
+        (b"ID3", "audio/mpeg"),
+        (b"fLaC", "audio/flac"),
+        (b"OggS", "audio/ogg"),
+    ];
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(magic, _)| bytes.starts_with(magic)) {
+        return Some(mime);
+    }
+
+    if bytes.starts_with(b"\x7fELF") {
+        // The object file type follows the 16 byte identification
+        return Some(match bytes.get(16) {
+            Some(1) => "application/x-object",
+            Some(3) => "application/x-sharedlib",
+            Some(4) => "application/x-coredump",
+            _ => "application/x-executable",
+        });
+    }
+    if bytes.starts_with(b"RIFF") && bytes.len() >= 12 {
+        return match &bytes[8..12] {
+            b"WEBP" => Some("image/webp"),
+            b"WAVE" => Some("audio/wav"),
+            b"AVI " => Some("video/x-msvideo"),
+            _ => None,
+        };
+    }
+    if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" {
+        return Some(match &bytes[8..12] {
+            b"qt  " => "video/quicktime",
+            b"M4A " => "audio/mp4",
+            b"heic" | b"heix" | b"mif1" => "image/heic",
+            b"avif" => "image/avif",
+            _ => "video/mp4",
+        });
+    }
+    if bytes.starts_with(b"\x1a\x45\xdf\xa3") {
+        let webm = bytes.windows(4).take(64).any(|window| window == b"webm");
+        return Some(if webm { "video/webm" } else { "video/x-matroska" });
+    }
+    if bytes.len() >= 2 && bytes[0] == 0xff && bytes[1] & 0xe0 == 0xe0 && bytes[1] & 0x06 != 0 {
+        // An MPEG audio frame header without ID3 tag
+        return Some("audio/mpeg");
+    }
+    if bytes.get(257..262) == Some(b"ustar") {
+        return Some("application/x-tar");
+    }
+    if bytes.starts_with(b"BM") && bytes.len() >= 14 && bytes[6..10] == [0, 0, 0, 0] {
+        return Some("image/bmp");
+    }
+    None
+}
+
+/// Maps a MIME type to a broad kind of content, such as `image`, `archive`
+/// or `executable`, for coarse grouping.
+pub fn mime_kind(mime: &str) -> &'static str {
+    let essence = mime.split(';').next().unwrap_or_default().trim();
+    match essence {
+        "inode/directory" => "directory",
+        "inode/symlink" => "symlink",
+        "inode/x-empty" => "empty",
+        "application/octet-stream" => "binary",
+        "application/pdf" | "application/postscript" => "document",
+        "application/vnd.sqlite3" => "database",
+        "application/zip" | "application/gzip" | "application/x-bzip2" | "application/x-xz" | "application/zstd"
+        | "application/x-7z-compressed" | "application/vnd.rar" | "application/x-tar" => "archive",
+        "application/x-executable" | "application/x-sharedlib" | "application/x-object" | "application/x-coredump"
+        | "application/x-mach-binary" | "application/vnd.microsoft.portable-executable" | "application/wasm"
+        | "application/java-vm" => "executable",
+        _ => match essence.split('/').next().unwrap_or_default() {
+            "image" => "image",
+            "audio" => "audio",
+            "video" => "video",
+            "text" => "text",
+            "inode" => "special",
+            _ => "other",
+        },
+    }
+}
+
+/// Whether the leading bytes of a file (up to `SNIFF_BYTES`) show that it is
+/// binary: they contain a NUL byte, which text files never do, and do not
+/// start with a byte order mark, as UTF-16 text is full of NUL bytes.
+///
+/// `sniff_mime`, `text_stats` and `count_matches` all decide with this test.
+pub fn is_binary(head: &[u8]) -> bool {
+    !BYTE_ORDER_MARKS.iter().any(|(mark, _)| head.starts_with(mark)) && head.contains(&0)
+}
+
+/// Line, word and character counts of a file, along with its encoding.
+///
+/// # Fields
+/// - `lines`: The number of lines; a last line without a trailing newline counts too
+/// - `words`: The number of sequences of characters separated by whitespace
+/// - `chars`: The number of characters if the contents are valid UTF-8, bytes otherwise
+/// - `is_binary`: Whether the first `SNIFF_BYTES` bytes look binary (see `is_binary`)
+/// - `is_utf8`: Whether the contents are valid UTF-8 (which includes plain ASCII)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextStats {
+    pub lines: u64,
+    pub words: u64,
+    pub chars: u64,
+    pub is_binary: bool,
+    pub is_utf8: bool,
+}
+
+/// Reads a whole file and counts its lines, words and characters.
+///
+/// The file is read in chunks, so memory use does not depend on its size.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be opened or read.
+pub fn text_stats(path: &Path) -> io::Result<TextStats> {
+    let mut reader = fs::File::open(path)?;
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut stats = TextStats { is_utf8: true, ..TextStats::default() };
+    let mut bytes = 0;
+    let mut in_word = false;
+    let mut last = None;
+    let mut head: Vec<u8> = Vec::new();
+    // The start of a UTF-8 sequence cut off by the end of the previous chunk
+    let mut pending: Vec<u8> = Vec::new();
+
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        let chunk = &buffer[..read];
+        let sniffed = (SNIFF_BYTES as usize).saturating_sub(head.len()).min(chunk.len());
+        head.extend_from_slice(&chunk[..sniffed]);
+        for &byte in chunk {
+            if byte == b'\n' {
+                stats.lines += 1;
+            }
+            let space = byte.is_ascii_whitespace();
+            if !space && !in_word {
+                stats.words += 1;
+            }
+            in_word = !space;
+            // Every character starts with exactly one byte that is not a continuation byte
+            if byte & 0xC0 != 0x80 {
+                stats.chars += 1;
+            }
+        }
+        bytes += read as u64;
+        last = chunk.last().copied();
+
+        if stats.is_utf8 {
+            pending.extend_from_slice(chunk);
+            match std::str::from_utf8(&pending) {
+                Ok(_) => pending.clear(),
+                Err(e) if e.error_len().is_none() => {
+                    pending.drain(..e.valid_up_to());
+                }
+                Err(_) => stats.is_utf8 = false,
+            }
+        }
+    }
+
+    stats.is_binary = is_binary(&head);
+    if !pending.is_empty() {
+        stats.is_utf8 = false;
+    }
+    if !stats.is_utf8 {
+        stats.chars = bytes;
+    }
+    if last.is_some_and(|byte| byte != b'\n') {
+        stats.lines += 1;
+    }
+    Ok(stats)
+}
+
+/// Counts the matches of a pattern in the contents of a file, line by line.
+///
+/// Lines are matched without their line terminator, so patterns never span
+/// lines. The file is read through a buffer, so memory use depends on the
+/// length of the longest line rather than the size of the file.
+///
+/// # Arguments
+///
+/// * `path` - The file to search
+/// * `regex` - The pattern to search for
+/// * `skip_binary` - Whether to give up on files whose first `SNIFF_BYTES`
+///   bytes look binary (see `is_binary`)
+///
+/// # Returns
+///
+/// The number of non-overlapping matches, or `None` if the file was skipped as binary.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be opened or read.
+pub fn count_matches(path: &Path, regex: &Regex, skip_binary: bool) -> io::Result<Option<u64>> {
+    let mut file = fs::File::open(path)?;
+    // Check the first bytes up front, so a binary file without line breaks is never read whole
+    let mut head = Vec::new();
+    if skip_binary {
+        file.by_ref().take(SNIFF_BYTES).read_to_end(&mut head)?;
+        if is_binary(&head) {
+            return Ok(None);
+        }
+    }
+    let mut reader = BufReader::with_capacity(BUFFER_SIZE, Cursor::new(head).chain(file));
+    let mut line = Vec::new();
+    let mut count = 0;
+
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            return Ok(Some(count));
+        }
+
+        let text = line.strip_suffix(b"\n").unwrap_or(&line);
+        let text = text.strip_suffix(b"\r").unwrap_or(text);
+        count += regex.find_iter(text).count() as u64;
+    }
+}
+
+/// Cached content attributes of a single file.
+///
+/// Each attribute is computed at most once; `None` means the attribute does
+/// not apply (e.g. the file is a directory) or the file could not be read.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentCache {
+    hash: OnceLock<Option<String>>,
+    sha256: OnceLock<Option<String>>,
+    mime: OnceLock<Option<String>>,
+    stats: OnceLock<Option<TextStats>>,
+    searches: Searches,
+}
+
+/// The match counts of the content searches run on a file, by pattern and
+/// whether binary files are skipped; `None` counts files skipped as binary
+/// or unreadable.
+///
+/// Searches are recorded as conditions are evaluated, so the lock is never
+/// contended; it only makes the cache shareable like the `OnceLock`s.
+#[derive(Debug, Default)]
+struct Searches(Mutex<Vec<(String, bool, Option<u64>)>>);
+
+impl Searches {
+    fn entries(&self) -> Vec<(String, bool, Option<u64>)> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl Clone for Searches {
+    fn clone(&self) -> Self {
+        Searches(Mutex::new(self.entries()))
+    }
+}
+
+impl PartialEq for Searches {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries() == other.entries()
+    }
+}
+
+impl Eq for Searches {}
+
+impl ContentCache {
+    /// Returns the cached hash for `algorithm`, computing it with `compute` on first use.
+    pub fn hash(&self, algorithm: HashAlgorithm, compute: impl FnOnce() -> Option<String>) -> Option<&str> {
+        let cell = match algorithm {
+            HashAlgorithm::Xxh3 => &self.hash,
+            HashAlgorithm::Sha256 => &self.sha256,
+        };
+        cell.get_or_init(compute).as_deref()
+    }
+
+    /// Returns the cached MIME type, computing it with `compute` on first use.
+    pub fn mime(&self, compute: impl FnOnce() -> Option<String>) -> Option<&str> {
+        self.mime.get_or_init(compute).as_deref()
+    }
+
+    /// Returns the cached text statistics, computing them with `compute` on first use.
+    pub fn stats(&self, compute: impl FnOnce() -> Option<TextStats>) -> Option<TextStats> {
+        *self.stats.get_or_init(compute)
+    }
+
+    /// Returns the cached match count of a search for `pattern`, computing
+    /// it with `compute` on first use.
+    pub fn search(&self, pattern: &str, skip_binary: bool, compute: impl FnOnce() -> Option<u64>) -> Option<u64> {
+        let cached = self.searches.0.lock().unwrap().iter().find_map(|(searched, skipped, count)| {
+            (searched == pattern && *skipped == skip_binary).then_some(*count)
+        });
+        if let Some(count) = cached {
+            return count;
+        }
+        let count = compute();
+        self.searches.0.lock().unwrap().push((pattern.to_string(), skip_binary, count));
+        count
+    }
+
+    /// Returns the total match count of the searches run so far, or `None`
+    /// if none produced a count.
+    pub fn matches(&self) -> Option<u64> {
+        self.searches.0.lock().unwrap().iter().filter_map(|(.., count)| *count).reduce(|total, count| total + count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_file() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("lsdir-content-hash-{}", std::process::id()));
+        let prefix = dir.join(format!("lsdir-content-prefix-{}", std::process::id()));
+        fs::write(&path, "hello world").unwrap();
+        fs::write(&prefix, "hello").unwrap();
+
+        let sha256 = hash_file(&path, HashAlgorithm::Sha256, None).unwrap();
+        assert_eq!(sha256, "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9");
+        let full = hash_file(&path, HashAlgorithm::Xxh3, None).unwrap();
+        assert_eq!(full.len(), 32);
+        // A limited hash equals the hash of the prefix alone
+        let partial = hash_file(&path, HashAlgorithm::Xxh3, Some(5)).unwrap();
+        assert_eq!(partial, hash_file(&prefix, HashAlgorithm::Xxh3, None).unwrap());
+        assert_ne!(partial, full);
+
+        fs::remove_file(path).unwrap();
+        fs::remove_file(prefix).unwrap();
+    }
+
+    #[test]
+    fn test_sniff_mime() {
+        assert_eq!(sniff_mime(b"\x89PNG\r\n\x1a\n\x00\x00", true), "image/png");
+        assert_eq!(sniff_mime(b"%PDF-1.7\n", true), "application/pdf");
+        assert_eq!(sniff_mime(b"PK\x03\x04rest", true), "application/zip");
+        let mut elf = b"\x7fELF\x02\x01\x01".to_vec();
+        elf.resize(18, 0);
+        elf[16] = 3;
+        assert_eq!(sniff_mime(&elf, true), "application/x-sharedlib");
+        let mut tar = vec![b'a'; 300];
+        tar[257..262].copy_from_slice(b"ustar");
+        assert_eq!(sniff_mime(&tar, true), "application/x-tar");
+        assert_eq!(sniff_mime(b"RIFF\x00\x00\x00\x00WEBPVP8 ", true), "image/webp");
+
+        assert_eq!(sniff_mime(b"hello\n", true), "text/plain; charset=us-ascii");
+        assert_eq!(sniff_mime("gr\u{fc}\u{df}e".as_bytes(), true), "text/plain; charset=utf-8");
+        assert_eq!(sniff_mime(b"\xff\xfeh\x00i\x00", true), "text/plain; charset=utf-16le");
+        assert_eq!(sniff_mime(b"#!/bin/sh\necho hi\n", true), "text/x-shellscript; charset=us-ascii");
+        assert_eq!(sniff_mime(b"#!/usr/bin/env python3\n", true), "text/x-script.python; charset=us-ascii");
+        assert_eq!(sniff_mime(b"  <!DOCTYPE html><html>", true), "text/html; charset=us-ascii");
+        assert_eq!(sniff_mime(b"<?xml version=\"1.0\"?><svg>", true), "image/svg+xml");
+        assert_eq!(sniff_mime(b"\x01\x02\x00\x03", true), "application/octet-stream");
+        assert_eq!(sniff_mime(b"", true), "inode/x-empty");
+
+        // A multi-byte character cut off by the end of the sample is still text
+        let cut = &"\u{fc}".as_bytes()[..1];
+        assert_eq!(sniff_mime(&[b"abc", cut].concat(), false), "text/plain; charset=utf-8");
+        assert_eq!(sniff_mime(&[b"abc", cut].concat(), true), "application/octet-stream");
+    }
+
+    #[test]
+    fn test_mime_kind() {
+        assert_eq!(mime_kind("image/png"), "image");
+        assert_eq!(mime_kind("text/plain; charset=utf-8"), "text");
+        assert_eq!(mime_kind("application/gzip"), "archive");
+        assert_eq!(mime_kind("application/x-executable"), "executable");
+        assert_eq!(mime_kind("inode/directory"), "directory");
+        assert_eq!(mime_kind("inode/fifo"), "special");
+        assert_eq!(mime_kind("application/octet-stream"), "binary");
+    }
+
+    #[test]
+    fn test_text_stats() {
+        let path = std::env::temp_dir().join(format!("lsdir-content-stats-{}", std::process::id()));
+        fs::write(&path, "fn main() {\n    println!(\"grüß\");\n}").unwrap();
+        let stats = text_stats(&path).unwrap();
+        assert_eq!(stats.lines, 3);
+        assert_eq!(stats.words, 5);
+        assert_eq!(stats.chars, 35);
+        assert!(stats.is_utf8);
+        assert!(!stats.is_binary);
+
+        // Multi-byte characters split across chunks are still valid
+        let long = format!("{}ü\n", "a".repeat(BUFFER_SIZE - 1));
+        fs::write(&path, &long).unwrap();
+        let stats = text_stats(&path).unwrap();
+        assert!(stats.is_utf8);
+        assert_eq!(stats.chars, BUFFER_SIZE as u64 + 1);
+        assert_eq!(stats.lines, 1);
+
+        fs::write(&path, b"\x7fELF\x00\xff\xfe").unwrap();
+        let stats = text_stats(&path).unwrap();
+        assert!(stats.is_binary);
+        assert!(!stats.is_utf8);
+        assert_eq!(stats.chars, 7);
+
+        // Only the first bytes decide, as for the MIME type and content searches
+        let mut late_nul = "a".repeat(SNIFF_BYTES as usize).into_bytes();
+        late_nul.push(0);
+        fs::write(&path, &late_nul).unwrap();
+        assert!(!text_stats(&path).unwrap().is_binary);
+
+        // UTF-16 text is full of NUL bytes, but its byte order mark identifies it as text
+        let utf16: Vec<u8> = [0xFF, 0xFE].into_iter().chain("hi\n".encode_utf16().flat_map(u16::to_le_bytes)).collect();
+        fs::write(&path, &utf16).unwrap();
+        assert!(!text_stats(&path).unwrap().is_binary);
+        assert_eq!(sniff_file(&path).unwrap(), "text/plain; charset=utf-16le");
+
+        fs::write(&path, "").unwrap();
+        assert_eq!(text_stats(&path).unwrap(), TextStats { is_utf8: true, ..TextStats::default() });
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_count_matches() {
+        let path = std::env::temp_dir().join(format!("lsdir-content-matches-{}", std::process::id()));
+        fs::write(&path, "// TODO: one\r\nlet x = 1; // TODO two TODO\nno match\n").unwrap();
+        let todo = Regex::new("TODO").unwrap();
+        assert_eq!(count_matches(&path, &todo, true).unwrap(), Some(3));
+        // Lines are matched without their terminator
+        let line_end = Regex::new("one$").unwrap();
+        assert_eq!(count_matches(&path, &line_end, true).unwrap(), Some(1));
+
+        fs::write(&path, b"\x00\x01TODO\n").unwrap();
+        assert_eq!(count_matches(&path, &todo, true).unwrap(), None);
+        assert_eq!(count_matches(&path, &todo, false).unwrap(), Some(1));
+
+        // Lines spanning the sniffed bytes are still matched whole
+        let long = format!("{}TODO\nTODO\n", "a".repeat(SNIFF_BYTES as usize));
+        fs::write(&path, long).unwrap();
+        assert_eq!(count_matches(&path, &todo, true).unwrap(), Some(2));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_cache_computes_once() {
+        let cache = ContentCache::default();
+        assert_eq!(cache.hash(HashAlgorithm::Xxh3, || Some("abc".to_string())), Some("abc"));
+        assert_eq!(cache.hash(HashAlgorithm::Xxh3, || panic!("computed twice")), Some("abc"));
+        assert_eq!(cache.hash(HashAlgorithm::Sha256, || None), None);
+
+        assert_eq!(cache.matches(), None);
+        assert_eq!(cache.search("TODO", true, || Some(2)), Some(2));
+        assert_eq!(cache.search("TODO", true, || panic!("searched twice")), Some(2));
+        assert_eq!(cache.search("FIXME", true, || Some(0)), Some(0));
+        assert_eq!(cache.search("TODO", false, || None), None);
+        assert_eq!(cache.matches(), Some(2));
+    }
+}
@@ -1,80 +1,343 @@
-use std::{fmt::Display, fs::DirEntry};
+use std::{fmt::Display, fs, fs::DirEntry};
 use std::io::Result;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use chrono::{DateTime, Local};
+use regex::bytes::Regex;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+use crate::content::{count_matches, hash_file, mime_kind, sniff_file, text_stats, ContentCache, HashAlgorithm, TextStats};
+use crate::permissions;
+
+/// The kind of a directory entry, as reported without following symbolic links.
+///
+/// # Variants
+/// - `File`: A regular file
+/// - `Directory`: A directory
+/// - `Symlink`: A symbolic link, whatever it points to
+/// - `Fifo`: A named pipe
+/// - `Socket`: A Unix domain socket
+/// - `BlockDevice`: A block device
+/// - `CharDevice`: A character device
+/// - `Unknown`: Anything the platform reports but lsdir does not recognize
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FileType {
+    #[default]
+    File,
+    Directory,
+    Symlink,
+    Fifo,
+    Socket,
+    BlockDevice,
+    CharDevice,
+    Unknown,
+}
+
+impl From<fs::FileType> for FileType {
+    fn from(file_type: fs::FileType) -> Self {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            if file_type.is_fifo() {
+                return FileType::Fifo;
+            } else if file_type.is_socket() {
+                return FileType::Socket;
+            } else if file_type.is_block_device() {
+                return FileType::BlockDevice;
+            } else if file_type.is_char_device() {
+                return FileType::CharDevice;
+            }
+        }
+
+        if file_type.is_symlink() {
+            FileType::Symlink
+        } else if file_type.is_dir() {
+            FileType::Directory
+        } else if file_type.is_file() {
+            FileType::File
+        } else {
+            FileType::Unknown
+        }
+    }
+}
+
+impl FromStr for FileType {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "file" | "regular" | "f" => Ok(FileType::File),
+            "directory" | "dir" | "d" => Ok(FileType::Directory),
+            "symlink" | "link" | "l" => Ok(FileType::Symlink),
+            "fifo" | "pipe" | "p" => Ok(FileType::Fifo),
+            "socket" | "sock" | "s" => Ok(FileType::Socket),
+            "block_device" | "block" | "b" => Ok(FileType::BlockDevice),
+            "char_device" | "char" | "c" => Ok(FileType::CharDevice),
+            "unknown" => Ok(FileType::Unknown),
+            _ => Err(format!("Unknown file type: {}", s)),
+        }
+    }
+}
+
+impl Display for FileType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            FileType::File => "File",
+            FileType::Directory => "Directory",
+            FileType::Symlink => "Symlink",
+            FileType::Fifo => "FIFO",
+            FileType::Socket => "Socket",
+            FileType::BlockDevice => "Block device",
+            FileType::CharDevice => "Char device",
+            FileType::Unknown => "Unknown",
+        };
+        f.pad(name)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 /// Represents a file with its metadata.
 ///
 /// # Fields
 /// - `name`: The name of the file (excluding the path).
+/// - `path`: The path of the file relative to the directory being analyzed.
+/// - `depth`: How many directories deep the file is below the analyzed directory (1 for direct children).
 /// - `extension`: The file's extension (e.g., "txt", "rs").
 /// - `size`: The size of the file in bytes.
 /// - `modified`: The last modification time of the file.
 /// - `accessed`: The last access time of the file.
 /// - `created`: The creation time of the file.
-/// - `file_type`: The type of the file (e.g., "file", "directory", "symlink").
+/// - `file_type`: The type of the file (e.g., file, directory, symlink).
+/// - `link_target`: The path a symbolic link points to, as stored in the link.
+/// - `broken_link`: Whether the file is a symbolic link whose target does not exist.
+/// - `uid`: The numeric id of the owning user (0 on platforms without Unix ownership).
+/// - `gid`: The numeric id of the owning group.
+/// - `owner`: The name of the owning user, or its id if it has no name.
+/// - `group`: The name of the owning group, or its id if it has no name.
+/// - `mode`: The permission bits, including the setuid, setgid and sticky bits.
+/// - `inode`: The inode number (0 on platforms that do not report one).
+/// - `device`: The id of the device the file resides on.
+/// - `nlink`: The number of hard links to the file.
+/// - `disk_usage`: The disk space allocated to the file in bytes (512-byte blocks).
+/// - `full_path`: The path used to open the file, i.e. the analyzed directory joined with `path`.
+/// - `content`: Attributes derived from the file contents, computed on first use.
 pub struct File {
     pub name: String,
+    pub path: String,
+    pub depth: usize,
     pub extension: String,
     pub size: u64,
     pub modified: DateTime<Local>,
     pub accessed: DateTime<Local>,
     pub created: DateTime<Local>,
-    pub file_type: String,
+    pub file_type: FileType,
+    pub link_target: Option<String>,
+    pub broken_link: bool,
+    pub uid: u32,
+    pub gid: u32,
+    pub owner: String,
+    pub group: String,
+    pub mode: u32,
+    pub inode: u64,
+    pub device: u64,
+    pub nlink: u64,
+    pub disk_usage: u64,
+    pub full_path: PathBuf,
+    pub content: ContentCache,
 }
 
 impl File {
     /// Creates a `File` instance from a given directory entry (`DirEntry`).
     ///
     /// This method extracts metadata from the provided `DirEntry`, including the file name,
-    /// extension, size, modification time, access time, creation time, and determines whether
-    /// the entry is a directory or a file. Returns a `Result` containing the constructed `File`
-    /// on success, or an error if any metadata extraction fails.
+    /// extension, size, modification time, access time, creation time and file type.
+    /// Symbolic links are described by the link itself rather than their target; their
+    /// target path is read and checked for existence. Returns a `Result` containing the
+    /// constructed `File` on success, or an error if any metadata extraction fails.
     ///
     /// # Arguments
     ///
     /// * `entry` - A reference to a `DirEntry` from which to construct the `File`.
+    /// * `root` - The directory being analyzed, used to compute the relative path.
+    /// * `depth` - The depth of the entry below `root`.
     ///
     /// # Errors
     ///
     /// Returns an error if retrieving metadata or any of the time fields fails.
-    pub fn from_dir_entry(entry: &DirEntry) -> Result<Self> {
-        let metadata = entry.metadata()?;
+    pub fn from_dir_entry(entry: &DirEntry, root: &Path, depth: usize) -> Result<Self> {
+        let metadata = fs::symlink_metadata(entry.path())?;
+        let file_type = FileType::from(metadata.file_type());
         let name = entry.file_name().to_string_lossy().into_owned();
-        let extension = entry
-            .path()
+        let full_path = entry.path();
+        let path = full_path
+            .strip_prefix(root)
+            .unwrap_or(&full_path)
+            .to_string_lossy()
+            .into_owned();
+        let extension = full_path
             .extension()
             .and_then(|ext| ext.to_str())
             .unwrap_or("")
             .to_string();
+        let (link_target, broken_link) = if file_type == FileType::Symlink {
+            let target = fs::read_link(&full_path)?.to_string_lossy().into_owned();
+            (Some(target), fs::metadata(&full_path).is_err())
+        } else {
+            (None, false)
+        };
+
+        #[cfg(unix)]
+        let (uid, gid, mode, inode, device, nlink, disk_usage) = {
+            use std::os::unix::fs::MetadataExt;
+            (
+                metadata.uid(),
+                metadata.gid(),
+                metadata.mode() & 0o7777,
+                metadata.ino(),
+                metadata.dev(),
+                metadata.nlink(),
+                metadata.blocks() * 512,
+            )
+        };
+        #[cfg(not(unix))]
+        let (uid, gid, mode, inode, device, nlink, disk_usage) = (0, 0, 0, 0, 0, 1, metadata.len());
 
         Ok(Self {
             name,
+            path,
+            depth,
             extension,
             size: metadata.len(),
             modified: DateTime::<Local>::from(metadata.modified()?),
             accessed: DateTime::<Local>::from(metadata.accessed()?),
             created: DateTime::<Local>::from(metadata.created()?),
-            file_type: if metadata.is_dir() {
-                "Directory".to_string()
-            } else {
-                "File".to_string()
-            },
+            file_type,
+            link_target,
+            broken_link,
+            uid,
+            gid,
+            owner: permissions::user_name(uid),
+            group: permissions::group_name(gid),
+            mode,
+            inode,
+            device,
+            nlink,
+            disk_usage,
+            full_path,
+            content: ContentCache::default(),
         })
     }
-}
 
-impl Display for File {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{:<19} | {:<19} | {:<19} | {:<10} | {:>10} | {:<30}",
-            self.modified.format("%Y-%m-%d %H:%M:%S"),
-            self.accessed.format("%Y-%m-%d %H:%M:%S"),
-            self.created.format("%Y-%m-%d %H:%M:%S"),
-            self.file_type,
-            self.size,
-            self.name,
-        )
+    /// Returns the file name without its extension (`main` for `main.rs`).
+    pub fn stem(&self) -> String {
+        Path::new(&self.name)
+            .file_stem()
+            .map_or_else(|| self.name.clone(), |stem| stem.to_string_lossy().into_owned())
     }
-}
\ No newline at end of file
+
+    /// Returns the directory containing the file, relative to the analyzed
+    /// directory, or `.` for entries directly inside it.
+    ///
+    /// With `levels`, only the first `levels` directories of the parent are
+    /// kept, so `Some(1)` yields the top-level subdirectory a file lives in.
+    pub fn parent(&self, levels: Option<usize>) -> String {
+        let components: Vec<String> = Path::new(&self.path)
+            .parent()
+            .into_iter()
+            .flat_map(|parent| parent.components())
+            .take(levels.unwrap_or(usize::MAX))
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        if components.is_empty() {
+            ".".to_string()
+        } else {
+            components.join("/")
+        }
+    }
+
+    /// Returns the absolute path of the file, without resolving symbolic links.
+    pub fn abs_path(&self) -> String {
+        std::path::absolute(&self.full_path)
+            .unwrap_or_else(|_| self.full_path.clone())
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Returns the hash of the file contents, reading the file on first use.
+    ///
+    /// Only regular files have a content hash. If the file cannot be read, a
+    /// warning is printed once and `None` is returned.
+    pub fn hash(&self, algorithm: HashAlgorithm) -> Option<&str> {
+        self.content.hash(algorithm, || {
+            if self.file_type != FileType::File {
+                return None;
+            }
+            hash_file(&self.full_path, algorithm, None)
+                .map_err(|e| eprintln!("Warning: Could not hash file {:?}: {}", self.full_path, e))
+                .ok()
+        })
+    }
+
+    /// Returns the MIME type of the file, sniffing its first bytes on first use.
+    ///
+    /// Entries other than regular files get an `inode/...` type describing
+    /// what they are (e.g. `inode/directory`), like `file --mime-type` reports.
+    /// If the file cannot be read, a warning is printed once and `None` is
+    /// returned.
+    pub fn mime(&self) -> Option<&str> {
+        self.content.mime(|| match self.file_type {
+            FileType::File => sniff_file(&self.full_path)
+                .map_err(|e| eprintln!("Warning: Could not read file {:?}: {}", self.full_path, e))
+                .ok(),
+            FileType::Directory => Some("inode/directory".to_string()),
+            FileType::Symlink => Some("inode/symlink".to_string()),
+            FileType::Fifo => Some("inode/fifo".to_string()),
+            FileType::Socket => Some("inode/socket".to_string()),
+            FileType::BlockDevice => Some("inode/blockdevice".to_string()),
+            FileType::CharDevice => Some("inode/chardevice".to_string()),
+            FileType::Unknown => None,
+        })
+    }
+
+    /// Returns the broad kind of content of the file (e.g. `image`), derived from its MIME type.
+    pub fn kind(&self) -> Option<&'static str> {
+        self.mime().map(mime_kind)
+    }
+
+    /// Returns the text statistics of the file, reading it on first use.
+    ///
+    /// Only regular files have statistics. If the file cannot be read, a
+    /// warning is printed once and `None` is returned.
+    pub fn stats(&self) -> Option<TextStats> {
+        self.content.stats(|| {
+            if self.file_type != FileType::File {
+                return None;
+            }
+            text_stats(&self.full_path)
+                .map_err(|e| eprintln!("Warning: Could not read file {:?}: {}", self.full_path, e))
+                .ok()
+        })
+    }
+
+    /// Counts the matches of a pattern in the contents of a regular file.
+    ///
+    /// Each pattern is searched at most once per file, and its count is
+    /// cached for the `matches` field. Returns `None` for entries other than
+    /// regular files, for files skipped as binary, and for files that cannot
+    /// be read, in which case a warning is printed.
+    pub fn count_matches(&self, regex: &Regex, skip_binary: bool) -> Option<u64> {
+        if self.file_type != FileType::File {
+            return None;
+        }
+        self.content.search(regex.as_str(), skip_binary, || {
+            count_matches(&self.full_path, regex, skip_binary)
+                .map_err(|e| eprintln!("Warning: Could not read file {:?}: {}", self.full_path, e))
+                .ok()
+                .flatten()
+        })
+    }
+
+    /// Returns the statistics of a text file, or `None` for binary files and other entries.
+    pub fn text(&self) -> Option<TextStats> {
+        self.stats().filter(|stats| !stats.is_binary)
+    }
+}
@@ -1,137 +1,267 @@
 mod cli;
+mod content;
 mod file;
+mod output;
+mod permissions;
+mod query;
 mod utilities;
+mod walk;
 
 use std::str::FromStr;
 use std::collections::HashMap;
-use std::fs;
+use std::path::Path;
 use clap::Parser;
-use cli::Cli;
+use cli::{Cli, Command};
 
 
 use file::File;
-use utilities::filter::{filter, Predicate};
-use utilities::group::{group, GroupingOperator};
-use utilities::aggregate::{AggregateFunction};
-
-use crate::utilities::aggregate::{count, sum, max, min, avg};
+use content::HashAlgorithm;
+use output::{print_aggregated, print_duplicates, print_listing};
+use query::Query;
+use walk::{walk, WalkOptions};
+use utilities::field::{parse_columns, Field, DEFAULT_COLUMNS};
+use utilities::duplicates::find_duplicates;
+use utilities::filter::{filter, Expr};
+use utilities::group::{group, group_multi, GroupingOperator};
+use utilities::aggregate::{AggregateFunction, Having};
+use utilities::order::{nest_groups, paginate, sort_files, sort_groups, OrderKey};
+use utilities::time::{parse_date_format, set_date_format, set_zone, Zone};
 
 fn main() -> std::io::Result<()> {
     let args = Cli::parse();
 
-    // Get directory path (default to current directory)
-    let dir_path = args.path.as_deref().unwrap_or(".");
-
-    // Read files from directory
-    let files = read_directory(dir_path)?;
+    if let Err(e) = check_depths(&args) {
+        eprintln!("{}", e);
+        return Ok(());
+    }
 
-    let file_refs: Vec<&File> = files.iter().collect();
+    // Date literals in the query are parsed in the zone, so set it up first
+    if let Err(e) = configure_time(&args) {
+        eprintln!("{}", e);
+        return Ok(());
+    }
 
-    // Apply WHERE filter if specified
-    let filtered_files = if let Some(where_clause) = &args.r#where {
-        match Predicate::from_str(where_clause) {
-            Ok(condition) => filter(&file_refs, condition),
+    let query = match &args.command {
+        Some(Command::Query { .. }) if args.path.is_some() || args.r#where.is_some()
+            || !args.group_by.is_empty() || args.having.is_some() || !args.aggregate.is_empty()
+            || args.select.is_some() || !args.order_by.is_empty() || args.limit.is_some() || args.offset != 0 => {
+            eprintln!("Error: PATH, --where, --group-by, --having, --aggregate, --select, --order-by, --limit and --offset cannot be combined with a query; use the corresponding clauses instead");
+            return Ok(());
+        }
+        Some(Command::Query { sql }) => match query::parse(sql) {
+            Ok(query) => match check_nested(&args, &query) {
+                Ok(()) => query,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return Ok(());
+                }
+            },
             Err(e) => {
-                eprintln!("Error parsing WHERE condition: {}", e);
+                eprintln!("Error parsing query: {}", e.render(sql));
                 return Ok(());
             }
+        },
+        Some(Command::Duplicates { .. }) if args.path.is_some() || !args.group_by.is_empty() || args.having.is_some()
+            || !args.aggregate.is_empty() || args.select.is_some() || !args.order_by.is_empty() => {
+            eprintln!("Error: PATH, --group-by, --having, --aggregate, --select and --order-by cannot be combined with duplicates");
+            return Ok(());
         }
-    } else {
-        file_refs
-    };
-    let grouped_files = if let Some(group_field) = &args.group_by {
-        match GroupingOperator::from_str(group_field) {
-            Ok(operator) => group(&filtered_files, operator),
+        Some(Command::Duplicates { path, sha256 }) => {
+            let mut query = match query_from_flags(&args) {
+                Ok(query) => query,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return Ok(());
+                }
+            };
+            query.path = path.clone();
+            let algorithm = if *sha256 { HashAlgorithm::Sha256 } else { HashAlgorithm::Xxh3 };
+            return run_duplicates(&args, query, algorithm);
+        }
+        None => match query_from_flags(&args) {
+            Ok(query) => query,
             Err(e) => {
-                eprintln!("Error parsing GROUP BY field: {}", e);
+                eprintln!("{}", e);
                 return Ok(());
             }
-        }
-    } else {
-        let mut map = HashMap::new();
-        map.insert(dir_path.to_string(), filtered_files);
-        map
+        },
     };
 
-    if let Some(aggregate) = &args.aggregate {
-        match AggregateFunction::from_str(aggregate) {
-            Ok(aggregate_function) => {
-               display_aggregated(&grouped_files, aggregate_function); 
-            }
-            Err(e) => {
-                eprintln!("Error parsing aggregate function: {}", e);
-            }
+    run(&args, query)
+}
+
+/// Rejects a `--min-depth` greater than `--max-depth`, which would list nothing.
+fn check_depths(args: &Cli) -> Result<(), String> {
+    match (args.min_depth, args.max_depth) {
+        (Some(min), Some(max)) if min > max => {
+            Err(format!("Error: --min-depth {} cannot be greater than --max-depth {}", min, max))
         }
-    } else {
-        display(&grouped_files);    
+        _ => Ok(()),
     }
+}
 
-
+/// Applies the `--tz` and `--date-format` options.
+fn configure_time(args: &Cli) -> Result<(), String> {
+    if let Some(tz) = &args.tz {
+        set_zone(Zone::from_str(tz).map_err(|e| format!("Error parsing time zone: {}", e))?);
+    }
+    if let Some(format) = &args.date_format {
+        set_date_format(parse_date_format(format).map_err(|e| format!("Error parsing date format: {}", e))?);
+    }
     Ok(())
 }
 
-fn read_directory(path: &str) -> std::io::Result<Vec<File>> {
-    let mut files = Vec::new();
-    for entry in fs::read_dir(path)? {
-        let entry = entry?;
-        match File::from_dir_entry(&entry) {
-            Ok(file) => files.push(file),
-            Err(e) => eprintln!("Warning: Could not read file {:?}: {}", entry.path(), e),
-        }
+/// Builds a `Query` from the `--where`, `--group-by`, `--having`, `--aggregate`, `--select`,
+/// `--order-by`, `--limit` and `--offset` flags.
+fn query_from_flags(args: &Cli) -> Result<Query, String> {
+    let filter = args.r#where.as_deref()
+        .map(Expr::from_str)
+        .transpose()
+        .map_err(|e| format!("Error parsing WHERE condition: {}", e))?;
+    let group_by = args.group_by.iter()
+        .map(|operator| GroupingOperator::from_str(operator))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Error parsing GROUP BY field: {}", e))?;
+    let having = args.having.as_deref()
+        .map(Having::from_str)
+        .transpose()
+        .map_err(|e| format!("Error parsing HAVING condition: {}", e))?;
+    let aggregates = args.aggregate.iter()
+        .map(|function| AggregateFunction::from_str(function))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Error parsing aggregate function: {}", e))?;
+    let columns = args.select.as_deref()
+        .map(parse_columns)
+        .transpose()
+        .map_err(|e| format!("Error parsing SELECT columns: {}", e))?
+        .unwrap_or_default();
+    if !columns.is_empty() && !aggregates.is_empty() {
+        return Err("Error: --select only applies to listings and cannot be combined with --aggregate".to_string());
     }
-    Ok(files)
+    let order_by = args.order_by.iter()
+        .map(|key| OrderKey::from_str(key))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Error parsing ORDER BY key: {}", e))?;
+
+    let query = Query {
+        path: args.path.clone(),
+        filter,
+        group_by,
+        having,
+        aggregates,
+        columns,
+        order_by,
+        limit: args.limit,
+        offset: args.offset,
+    };
+    check_nested(args, &query)?;
+    Ok(query)
 }
 
-fn display(files: &HashMap<String, Vec<&File>>) {
-    println!("   {:<19} | {:<19} | {:<19} | {:<10} | {:>10} | {:<30}",
-        "Modified", "Accessed", "Created", "Type", "Size (bytes)", "Name"
-    );
-    for (key, group) in files {
-        println!("{}", key);
-        for file in group {
-            println!("   {}", file);
-        }
+/// Rejects `--nested` unless the query aggregates over several group keys,
+/// as it does not change any other output.
+fn check_nested(args: &Cli, query: &Query) -> Result<(), String> {
+    if args.nested && (query.aggregates.is_empty() || query.group_by.len() < 2) {
+        return Err("Error: --nested only applies to aggregates over several group keys".to_string());
     }
-    
+    Ok(())
 }
 
-fn display_aggregated(files: &HashMap<String, Vec<&File>>, aggregate_function: AggregateFunction) {
-    match aggregate_function {
-        AggregateFunction::Count => {
-            let aggregated = count(files);
-            for (key, count) in aggregated {
-                println!("Group: {}, Count: {}\n", key, count);
-            }
+/// Reads the files below `dir_path`, descending into subdirectories if requested.
+fn read_files(args: &Cli, dir_path: &str) -> std::io::Result<Vec<File>> {
+    let walk_options = if args.recursive || args.max_depth.is_some() || args.min_depth.is_some() {
+        WalkOptions {
+            min_depth: args.min_depth.unwrap_or(1),
+            max_depth: args.max_depth,
         }
-        AggregateFunction::Sum(field) => {
-            let aggregated = sum(files, field.clone());
-            for (key, sum) in aggregated {
-                println!("{}, Sum of {}: {}\n", key, field, sum);
-            }
-        }
-        AggregateFunction::Max(field) => {
-            println!("{:<19} | {:<19} | {:<19} | {:<10} | {:>10} | {:<30}",
-                "Modified", "Accessed", "Created", "Type", "Size (bytes)", "Name"
-            );
-            let aggregated = max(files, field.clone());
-            for (key, max_value) in aggregated {
-                println!("{}, Max of {}:\n{}\n", key, field, max_value);
-            }
+    } else {
+        WalkOptions::default()
+    };
+    walk(Path::new(dir_path), &walk_options)
+}
+
+/// Finds and prints sets of duplicate files among those matching the query's filter.
+fn run_duplicates(args: &Cli, query: Query, algorithm: HashAlgorithm) -> std::io::Result<()> {
+    let files = read_files(args, query.path.as_deref().unwrap_or("."))?;
+    let file_refs: Vec<&File> = files.iter().collect();
+    let filtered_files = match &query.filter {
+        Some(expr) => filter(&file_refs, expr.clone()),
+        None => file_refs,
+    };
+
+    let sets = find_duplicates(&filtered_files, algorithm);
+    print_duplicates(args.format, &paginate(sets, query.offset, query.limit), args.human);
+    Ok(())
+}
+
+fn run(args: &Cli, query: Query) -> std::io::Result<()> {
+    // Get directory path (default to current directory)
+    let dir_path = query.path.as_deref().unwrap_or(".");
+
+    // Read files from directory, descending into subdirectories if requested
+    let files = read_files(args, dir_path)?;
+
+    let file_refs: Vec<&File> = files.iter().collect();
+
+    // Apply WHERE filter if specified
+    let filtered_files = match &query.filter {
+        Some(expr) => filter(&file_refs, expr.clone()),
+        None => file_refs,
+    };
+
+    let mut grouped_files = match &query.group_by[..] {
+        [] => {
+            let mut map = HashMap::new();
+            map.insert(dir_path.to_string(), filtered_files);
+            map
         }
-        AggregateFunction::Min(field) => {
-            println!("{:<19} | {:<19} | {:<19} | {:<10} | {:>10} | {:<30}",
-                "Modified", "Accessed", "Created", "Type", "Size (bytes)", "Name"
-            );
-            let aggregated = min(files, field.clone());
-            for (key, min_value) in aggregated {
-                println!("{}, Min of {}:\n{}\n", key, field, min_value);
-            }
+        [operator] => group(&filtered_files, operator.clone()),
+        operators => group_multi(&filtered_files, operators),
+    };
+
+    // Apply HAVING filter to whole groups before ordering them
+    if let Some(having) = &query.having {
+        grouped_files.retain(|_, files| having.matches(files));
+    }
+
+    let group_fields: Vec<Field> = query.group_by.iter().map(GroupingOperator::field).collect();
+
+    if !query.aggregates.is_empty() {
+        if args.nested && group_fields.len() > 1 {
+            let (nested_files, keys) = nest_groups(&grouped_files, &query.order_by, &query.group_by, query.offset, query.limit);
+            print_aggregated(args.format, &nested_files, &keys, &query.aggregates, &group_fields, true, args.human);
+        } else {
+            let keys = sort_groups(&grouped_files, &query.order_by, &query.group_by);
+            let keys = paginate(keys, query.offset, query.limit);
+            print_aggregated(args.format, &grouped_files, &keys, &query.aggregates, &group_fields, false, args.human);
         }
-        AggregateFunction::Avg(field) => {
-            let aggregated = avg(files, field.clone());
-            for (key, avg_value) in aggregated {
-                println!("{}, Avg of {}: {}\n", key, field, avg_value);
-            }
+    } else {
+        for group in grouped_files.values_mut() {
+            sort_files(group, &query.order_by);
+            *group = paginate(std::mem::take(group), query.offset, query.limit);
         }
+        let keys = sort_groups(&grouped_files, &query.order_by, &query.group_by);
+        let columns = if query.columns.is_empty() { &DEFAULT_COLUMNS[..] } else { &query.columns };
+        print_listing(args.format, &grouped_files, &keys, columns, &group_fields, args.human);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_depth_range_must_not_be_empty() {
+        let depths = |arguments: &[&str]| check_depths(&Cli::try_parse_from(arguments).unwrap());
+        assert_eq!(
+            depths(&["lsdir", "--min-depth", "3", "--max-depth", "2"]),
+            Err("Error: --min-depth 3 cannot be greater than --max-depth 2".to_string())
+        );
+        assert!(depths(&["lsdir", "--min-depth", "2", "--max-depth", "2"]).is_ok());
+        assert!(depths(&["lsdir", "--min-depth", "5"]).is_ok());
+        // The walk never produces depth 0, so the CLI rejects it
+        assert!(Cli::try_parse_from(["lsdir", "--max-depth", "0"]).is_err());
     }
-}
\ No newline at end of file
+}
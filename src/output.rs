@@ -0,0 +1,591 @@
+use crate::file::File;
+use crate::utilities::aggregate::{histogram, AggregateFunction, Bucket};
+use crate::utilities::duplicates::DuplicateSet;
+use crate::utilities::field::{Field, Value};
+use crate::utilities::group::{display_key, key_parts};
+use crate::utilities::size::format_size;
+
+use clap::ValueEnum;
+use std::collections::HashMap;
+
+/// The width of the longest bar in histogram charts, in characters.
+const BAR_WIDTH: usize = 40;
+
+/// Block characters drawing the last, partial character of a bar, by eighths.
+const PARTIAL_BLOCKS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+
+/// The format results are printed in.
+///
+/// Every structured format shares one schema: a `group` column holding the
+/// group key (the analyzed directory when not grouping), or `group_1` to
+/// `group_n` when grouping by several keys, followed by one column per listed
+/// field or computed aggregate, named by `Field::key` and
+/// `AggregateFunction::key`. Numbers are emitted unrounded and timestamps in
+/// ISO 8601 format with their UTC offset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Human-readable text (default)
+    #[default]
+    Table,
+    /// A JSON array of objects
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Comma-separated values with a header row
+    Csv,
+    /// Tab-separated values with a header row
+    Tsv,
+}
+
+/// Prints the files of each group, in the order given by `keys`.
+///
+/// # Arguments
+///
+/// * `format` - The output format
+/// * `files` - A map from group key to a vector of file references
+/// * `keys` - The group keys to print, in order
+/// * `columns` - The fields to show for each file
+/// * `group_fields` - The fields the files were grouped by, outermost first
+/// * `human` - Whether text tables show sizes with units instead of bytes
+pub fn print_listing(
+    format: Format,
+    files: &HashMap<String, Vec<&File>>,
+    keys: &[String],
+    columns: &[Field],
+    group_fields: &[Field],
+    human: bool,
+) {
+    if format == Format::Table {
+        display(files, keys, columns, human);
+        return;
+    }
+
+    let header: Vec<String> = key_columns(group_fields)
+        .into_iter()
+        .chain(columns.iter().map(|field| field.key().to_string()))
+        .collect();
+    let rows: Vec<Vec<Value>> = keys.iter()
+        .flat_map(|key| files[key].iter().map(move |file| (key, file)))
+        .map(|(key, file)| {
+            key_values(key, group_fields)
+                .into_iter()
+                .chain(columns.iter().map(|field| field.value(file)))
+                .collect()
+        })
+        .collect();
+    write_records(format, &header, &rows);
+}
+
+/// Prints one row per group with a column per aggregate, in the order given by `keys`.
+///
+/// Each row is built in one pass over its group with `AggregateFunction::evaluate`,
+/// so only the groups in `keys` are aggregated.
+///
+/// Every `max` and `min` aggregate is followed by a column holding the path
+/// of the file with the extreme value, keyed `<aggregate key>_path`.
+///
+/// Histograms are drawn as bar charts below each row of text tables. In
+/// structured formats they become one column per bucket found in any group,
+/// keyed `<aggregate key>:<bucket>` and holding the number of files, so
+/// every record has the same columns.
+///
+/// With several group keys, `keys` may contain subtotal groups whose composite
+/// keys have fewer parts (see `nest_groups`); their missing parts are empty.
+/// When `nested` is set, text tables show the hierarchy as a single indented
+/// column instead of one column per key part.
+///
+/// # Arguments
+///
+/// * `format` - The output format
+/// * `files` - A map from group key to a vector of file references
+/// * `keys` - The group keys to print, in order
+/// * `aggregates` - The aggregates to compute for each group
+/// * `group_fields` - The fields the files were grouped by, outermost first
+/// * `nested` - Whether to render a nested hierarchy in text tables
+/// * `human` - Whether text tables show sizes with units instead of bytes
+pub fn print_aggregated(
+    format: Format,
+    files: &HashMap<String, Vec<&File>>,
+    keys: &[String],
+    aggregates: &[AggregateFunction],
+    group_fields: &[Field],
+    nested: bool,
+    human: bool,
+) {
+    let nested_table = nested && format == Format::Table;
+    let mut header = key_columns(group_fields);
+    let mut titles = if group_fields.len() <= 1 || nested_table {
+        vec!["Group".to_string()]
+    } else {
+        group_fields.iter().map(|field| field.to_string()).collect()
+    };
+    let mut sizes = vec![false; titles.len()];
+    let mut numeric = vec![false; titles.len()];
+    let table = format == Format::Table;
+    let groups: Vec<&[&File]> = keys.iter()
+        .map(|key| files.get(key).map_or(&[][..], Vec::as_slice))
+        .collect();
+
+    // Histograms need the buckets of every group before their columns are known
+    let histograms: Vec<Vec<Vec<Bucket>>> = aggregates.iter()
+        .map(|function| match function {
+            AggregateFunction::Histogram(aggregator, buckets) => {
+                groups.iter().map(|group| histogram(group, aggregator, buckets)).collect()
+            }
+            _ => Vec::new(),
+        })
+        .collect();
+    let bucket_labels: Vec<Vec<String>> = histograms.iter()
+        .map(|histograms| {
+            let mut buckets: Vec<&Bucket> = histograms.iter().flatten().collect();
+            buckets.sort_by(|a, b| a.rank.cmp(&b.rank).then_with(|| a.label.cmp(&b.label)));
+            buckets.dedup_by(|a, b| a.label == b.label);
+            buckets.into_iter().map(|bucket| bucket.label.clone()).collect()
+        })
+        .collect();
+
+    for (function, labels) in aggregates.iter().zip(&bucket_labels) {
+        match function {
+            AggregateFunction::Histogram(..) if table => {}
+            AggregateFunction::Histogram(..) => {
+                for label in labels {
+                    header.push(format!("{}:{}", function.key(), label));
+                    titles.push(label.clone());
+                    sizes.push(false);
+                    numeric.push(true);
+                }
+            }
+            _ => {
+                header.push(function.key());
+                titles.push(function.to_string());
+                sizes.push(human && function.in_bytes());
+                numeric.push(function.is_numeric());
+                if let AggregateFunction::Max(_) | AggregateFunction::Min(_) = function {
+                    header.push(format!("{}_path", function.key()));
+                    titles.push(format!("{} (path)", function));
+                    sizes.push(false);
+                    numeric.push(false);
+                }
+            }
+        }
+    }
+
+    let rows: Vec<Vec<Value>> = keys.iter()
+        .zip(&groups)
+        .enumerate()
+        .map(|(index, (key, group))| {
+            let mut row = if nested_table {
+                let parts = key_parts(key);
+                let indent = "  ".repeat(parts.len() - 1);
+                vec![Value::Text(format!("{}{}", indent, parts[parts.len() - 1]))]
+            } else {
+                key_values(key, group_fields)
+            };
+            for ((function, histograms), labels) in aggregates.iter().zip(&histograms).zip(&bucket_labels) {
+                match function {
+                    AggregateFunction::Histogram(..) if table => {}
+                    AggregateFunction::Histogram(..) => row.extend(labels.iter().map(|label| {
+                        let bucket = histograms[index].iter().find(|bucket| bucket.label == *label);
+                        Value::Integer(bucket.map_or(0, |bucket| bucket.count))
+                    })),
+                    AggregateFunction::Max(aggregator) | AggregateFunction::Min(aggregator) => {
+                        let selected = function.select(group);
+                        row.push(selected.map_or(Value::Null, |file| aggregator.field().value(file)));
+                        row.push(selected.map_or(Value::Null, |file| Field::Path.value(file)));
+                    }
+                    _ => row.push(function.evaluate(group)),
+                }
+            }
+            row
+        })
+        .collect();
+
+    if table {
+        let charts: Vec<Chart> = aggregates.iter()
+            .zip(histograms)
+            .filter(|(function, _)| matches!(function, AggregateFunction::Histogram(..)))
+            .map(|(function, histograms)| Chart { title: function.to_string(), histograms })
+            .collect();
+        print!("{}", render_aggregated(&titles, &rows, &numeric, &sizes, &charts));
+    } else {
+        write_records(format, &header, &rows);
+    }
+}
+
+/// Prints sets of duplicate files, as found by `find_duplicates`.
+///
+/// Text tables show one block per set followed by a summary of the total
+/// reclaimable space. Structured formats write one record per file with the
+/// columns `set` (numbered from 1), `hash`, `size`, `count`, `reclaimable`
+/// and `path`, where `count` and `reclaimable` describe the whole set.
+///
+/// # Arguments
+///
+/// * `format` - The output format
+/// * `sets` - The duplicate sets to print, in order
+/// * `human` - Whether text tables show sizes with units instead of bytes
+pub fn print_duplicates(format: Format, sets: &[DuplicateSet], human: bool) {
+    if format == Format::Table {
+        let bytes = |size: u64| if human { format_size(size as f64) } else { format!("{} bytes", size) };
+        for set in sets {
+            println!(
+                "{} files of {}, {} reclaimable (hash {})",
+                set.files.len(),
+                bytes(set.size),
+                bytes(set.reclaimable()),
+                set.hash
+            );
+            for file in &set.files {
+                println!("   {}", file.path);
+            }
+            println!();
+        }
+        let reclaimable: u64 = sets.iter().map(DuplicateSet::reclaimable).sum();
+        println!("Duplicate sets: {}, reclaimable: {}", sets.len(), bytes(reclaimable));
+        return;
+    }
+
+    let header: Vec<String> = ["set", "hash", "size", "count", "reclaimable", "path"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    let rows: Vec<Vec<Value>> = sets
+        .iter()
+        .enumerate()
+        .flat_map(|(index, set)| {
+            set.files.iter().map(move |file| {
+                vec![
+                    Value::Integer(index as u64 + 1),
+                    Value::Text(set.hash.clone()),
+                    Value::Integer(set.size),
+                    Value::Integer(set.files.len() as u64),
+                    Value::Integer(set.reclaimable()),
+                    Value::Text(file.path.clone()),
+                ]
+            })
+        })
+        .collect();
+    write_records(format, &header, &rows);
+}
+
+/// Names the columns holding the group key in structured output.
+fn key_columns(group_fields: &[Field]) -> Vec<String> {
+    if group_fields.len() <= 1 {
+        vec!["group".to_string()]
+    } else {
+        (1..=group_fields.len()).map(|index| format!("group_{}", index)).collect()
+    }
+}
+
+/// Splits a group key into one value per key column, leaving the inner parts
+/// missing from subtotal keys empty.
+fn key_values(key: &str, group_fields: &[Field]) -> Vec<Value> {
+    let parts = key_parts(key);
+    (0..group_fields.len().max(1))
+        .map(|index| parts.get(index).map_or(Value::Null, |part| Value::Text(part.to_string())))
+        .collect()
+}
+
+/// Writes rows of values in one of the structured formats.
+fn write_records(format: Format, header: &[String], rows: &[Vec<Value>]) {
+    match format {
+        Format::Table => unreachable!("tables are printed by the display functions"),
+        Format::Json => {
+            if rows.is_empty() {
+                println!("[]");
+                return;
+            }
+            println!("[");
+            for (index, row) in rows.iter().enumerate() {
+                let separator = if index + 1 < rows.len() { "," } else { "" };
+                println!("  {}{}", json_object(header, row), separator);
+            }
+            println!("]");
+        }
+        Format::Ndjson => {
+            for row in rows {
+                println!("{}", json_object(header, row));
+            }
+        }
+        Format::Csv => {
+            println!("{}", header.iter().map(|name| csv_field(name)).collect::<Vec<_>>().join(","));
+            for row in rows {
+                let fields: Vec<String> = row.iter().map(|value| csv_field(&value.to_plain_string())).collect();
+                println!("{}", fields.join(","));
+            }
+        }
+        Format::Tsv => {
+            println!("{}", header.iter().map(|name| tsv_field(name)).collect::<Vec<_>>().join("\t"));
+            for row in rows {
+                let fields: Vec<String> = row.iter().map(|value| tsv_field(&value.to_plain_string())).collect();
+                println!("{}", fields.join("\t"));
+            }
+        }
+    }
+}
+
+fn json_object(header: &[String], row: &[Value]) -> String {
+    let members: Vec<String> = header.iter()
+        .zip(row)
+        .map(|(name, value)| format!("{}:{}", json_string(name), json_value(value)))
+        .collect();
+    format!("{{{}}}", members.join(","))
+}
+
+fn json_value(value: &Value) -> String {
+    match value {
+        Value::Boolean(flag) => flag.to_string(),
+        Value::Integer(number) => number.to_string(),
+        Value::Float(number) if number.is_finite() => number.to_string(),
+        Value::Float(_) | Value::Null => "null".to_string(),
+        Value::Text(_) | Value::Time(_) => json_string(&value.to_plain_string()),
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Quotes a CSV field if needed, following RFC 4180.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Escapes characters that would break the TSV structure.
+fn tsv_field(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Computes the width of each column so it fits its title and every cell.
+fn column_widths(titles: &[String], rows: &[Vec<String>]) -> Vec<usize> {
+    titles.iter()
+        .enumerate()
+        .map(|(index, title)| {
+            rows.iter()
+                .map(|row| row[index].chars().count())
+                .chain(std::iter::once(title.chars().count()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Pads and joins the cells of one table row, right-aligning numeric columns.
+fn render_row(cells: &[String], widths: &[usize], numeric: &[bool]) -> String {
+    cells.iter()
+        .zip(widths)
+        .zip(numeric)
+        .map(|((cell, width), numeric)| {
+            if *numeric {
+                format!("{:>width$}", cell, width = width)
+            } else {
+                format!("{:<width$}", cell, width = width)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+        .trim_end()
+        .to_string()
+}
+
+/// Formats a table cell, with units if it holds a size and `human` is set.
+fn cell(value: &Value, human: bool) -> String {
+    match value {
+        Value::Integer(bytes) if human => format_size(*bytes as f64),
+        Value::Float(bytes) if human => format_size(*bytes),
+        _ => value.to_string(),
+    }
+}
+
+/// Prints a text table whose column widths fit the widest header or value.
+fn display(files: &HashMap<String, Vec<&File>>, keys: &[String], columns: &[Field], human: bool) {
+    let titles: Vec<String> = columns.iter().map(|field| field.to_string()).collect();
+    let numeric: Vec<bool> = columns.iter().map(Field::is_numeric).collect();
+    let sizes: Vec<bool> = columns.iter().map(|field| human && field.is_size()).collect();
+    let rows: Vec<Vec<String>> = keys.iter()
+        .flat_map(|key| files[key].iter())
+        .map(|file| columns.iter().zip(&sizes).map(|(field, size)| cell(&field.value(file), *size)).collect())
+        .collect();
+    let widths = column_widths(&titles, &rows);
+
+    println!("   {}", render_row(&titles, &widths, &numeric));
+    let mut rows = rows.iter();
+    for key in keys {
+        println!("{}", display_key(key));
+        for row in rows.by_ref().take(files[key].len()) {
+            println!("   {}", render_row(row, &widths, &numeric));
+        }
+    }
+}
+
+/// A histogram aggregate to draw below the rows of a text table.
+struct Chart {
+    /// The title shown above each chart, e.g. `Histogram of Size`
+    title: String,
+    /// The buckets of each row's group, in row order
+    histograms: Vec<Vec<Bucket>>,
+}
+
+/// Renders aggregate rows as a text table, with the histograms of each row's
+/// group drawn as bar charts below it.
+///
+/// Columns flagged in `numeric` are right-aligned, whatever their values in
+/// a given row, and columns flagged in `sizes` are shown with units. Bars of a chart are
+/// scaled to the largest bucket of that histogram across all rows, so charts
+/// of different groups can be compared.
+fn render_aggregated(titles: &[String], rows: &[Vec<Value>], numeric: &[bool], sizes: &[bool], charts: &[Chart]) -> String {
+    let rows: Vec<Vec<String>> = rows.iter()
+        .map(|row| row.iter().zip(sizes).map(|(value, size)| cell(value, *size)).collect())
+        .collect();
+    let widths = column_widths(titles, &rows);
+    let scales: Vec<u64> = charts.iter()
+        .map(|chart| chart.histograms.iter().flatten().map(|bucket| bucket.count).max().unwrap_or(0))
+        .collect();
+
+    let mut output = render_row(titles, &widths, numeric) + "\n";
+    for (index, row) in rows.iter().enumerate() {
+        output += &render_row(row, &widths, numeric);
+        output.push('\n');
+        for (chart, scale) in charts.iter().zip(&scales) {
+            output += &format!("  {}\n", chart.title);
+            output += &render_chart(&chart.histograms[index], *scale);
+        }
+    }
+    output
+}
+
+/// Renders the buckets of a histogram as lines of labelled bars, each
+/// followed by its count, with bars as long as `BAR_WIDTH` for `scale` files.
+fn render_chart(buckets: &[Bucket], scale: u64) -> String {
+    let label_width = buckets.iter().map(|bucket| bucket.label.chars().count()).max().unwrap_or(0);
+    let count_width = buckets.iter().map(|bucket| bucket.count.to_string().len()).max().unwrap_or(0);
+    buckets.iter()
+        .map(|bucket| {
+            format!(
+                "    {:<label_width$} | {:>count_width$} {}\n",
+                bucket.label,
+                bucket.count,
+                bar(bucket.count, scale),
+            )
+        })
+        .collect()
+}
+
+/// Draws a bar of `count` out of `scale` files, in eighths of a character,
+/// keeping a sliver visible for any non-zero count.
+fn bar(count: u64, scale: u64) -> String {
+    if count == 0 || scale == 0 {
+        return String::new();
+    }
+    let eighths = ((count as f64 / scale as f64 * (BAR_WIDTH * 8) as f64).round() as usize).max(1);
+    "█".repeat(eighths / 8) + PARTIAL_BLOCKS[eighths % 8]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_string_escaping() {
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), "\"a\\\"b\\\\c\\nd\\u0001\"");
+    }
+
+    #[test]
+    fn test_json_object_typed_values() {
+        let header = vec!["group".to_string(), "size".to_string(), "avg_size".to_string()];
+        let row = vec![Value::Text("rs".to_string()), Value::Integer(42), Value::Float(1.5)];
+        assert_eq!(json_object(&header, &row), "{\"group\":\"rs\",\"size\":42,\"avg_size\":1.5}");
+    }
+
+    #[test]
+    fn test_render_row_alignment() {
+        let titles = vec!["Group".to_string(), "Count".to_string()];
+        let rows = vec![vec!["txt".to_string(), "12".to_string()], vec!["markdown".to_string(), "3".to_string()]];
+        let widths = column_widths(&titles, &rows);
+        assert_eq!(widths, vec![8, 5]);
+        assert_eq!(render_row(&rows[0], &widths, &[false, true]), "txt      |    12");
+    }
+
+    #[test]
+    fn test_key_values_for_subtotals() {
+        let fields = [Field::Extension, Field::Modified];
+        assert_eq!(key_columns(&fields), vec!["group_1", "group_2"]);
+        assert_eq!(key_columns(&[]), vec!["group"]);
+        assert_eq!(
+            key_values("rs", &fields),
+            vec![Value::Text("rs".to_string()), Value::Null]
+        );
+        assert_eq!(
+            key_values("rs\x002026", &fields),
+            vec![Value::Text("rs".to_string()), Value::Text("2026".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_bar_lengths() {
+        assert_eq!(bar(10, 10), "█".repeat(BAR_WIDTH));
+        assert_eq!(bar(5, 10), "█".repeat(BAR_WIDTH / 2));
+        assert_eq!(bar(1, 1000), "▏");
+        assert_eq!(bar(0, 10), "");
+        assert_eq!(bar(3, 64), "█▉");
+    }
+
+    #[test]
+    fn test_render_histogram_chart() {
+        let bucket = |label: &str, rank: u64, count: u64| Bucket { label: label.to_string(), rank, count };
+        let titles = vec!["Group".to_string(), "Count".to_string()];
+        let rows = vec![
+            vec![Value::Text("rs".to_string()), Value::Integer(12)],
+            vec![Value::Text("txt".to_string()), Value::Integer(1)],
+        ];
+        let chart = Chart {
+            title: "Histogram of Size".to_string(),
+            histograms: vec![vec![bucket("0–4K", 0, 2), bucket("4K–1M", 4096, 10)], vec![bucket("0–4K", 0, 1)]],
+        };
+        let output = render_aggregated(&titles, &rows, &[false, true], &[false, false], &[chart]);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "Group | Count");
+        assert_eq!(lines[1], "rs    |    12");
+        assert_eq!(lines[2], "  Histogram of Size");
+        assert_eq!(lines[3], format!("    0–4K  |  2 {}", "█".repeat(8)));
+        assert_eq!(lines[4], format!("    4K–1M | 10 {}", "█".repeat(BAR_WIDTH)));
+        assert_eq!(lines[5], "txt   |     1");
+        assert_eq!(lines[7], format!("    0–4K | 1 {}", "█".repeat(4)));
+        assert_eq!(lines.len(), 8);
+
+        // Alignment follows the column, even when the first row has no value
+        let titles = vec!["Group".to_string(), "Max of Lines".to_string()];
+        let rows = vec![vec![Value::Text(String::new()), Value::Null], vec![Value::Text("rs".to_string()), Value::Integer(12)]];
+        let output = render_aggregated(&titles, &rows, &[false, true], &[false, false], &[]);
+        assert_eq!(output.lines().nth(2), Some("rs    |           12"));
+    }
+
+    #[test]
+    fn test_csv_and_tsv_escaping() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(tsv_field("a\tb\nc"), "a\\tb\\nc");
+    }
+}
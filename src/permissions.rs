@@ -0,0 +1,186 @@
+//! Unix ownership and permission helpers.
+//!
+//! User and group ids are resolved to names by reading the local
+//! `/etc/passwd` and `/etc/group` databases once per run. Ids without an
+//! entry (e.g. files owned by deleted accounts, or accounts only known to a
+//! directory service) are shown as numbers, like `ls -l` does.
+
+use std::collections::HashMap;
+use std::fs;
+use std::sync::OnceLock;
+
+/// The set-user-ID bit.
+pub const SETUID: u32 = 0o4000;
+/// The set-group-ID bit.
+pub const SETGID: u32 = 0o2000;
+/// The sticky bit.
+pub const STICKY: u32 = 0o1000;
+
+static USERS: OnceLock<HashMap<u32, String>> = OnceLock::new();
+static GROUPS: OnceLock<HashMap<u32, String>> = OnceLock::new();
+
+/// Returns the name of the user with the given id, or the id itself if unknown.
+pub fn user_name(uid: u32) -> String {
+    USERS
+        .get_or_init(|| read_database("/etc/passwd"))
+        .get(&uid)
+        .cloned()
+        .unwrap_or_else(|| uid.to_string())
+}
+
+/// Returns the name of the group with the given id, or the id itself if unknown.
+pub fn group_name(gid: u32) -> String {
+    GROUPS
+        .get_or_init(|| read_database("/etc/group"))
+        .get(&gid)
+        .cloned()
+        .unwrap_or_else(|| gid.to_string())
+}
+
+fn read_database(path: &str) -> HashMap<u32, String> {
+    fs::read_to_string(path)
+        .map(|content| parse_database(&content))
+        .unwrap_or_default()
+}
+
+/// Parses a passwd or group style database, where each line starts with
+/// `name:password:id:`. Comments and malformed lines are skipped; when an id
+/// appears more than once, the first name wins.
+fn parse_database(content: &str) -> HashMap<u32, String> {
+    let mut names = HashMap::new();
+    for line in content.lines().filter(|line| !line.starts_with('#')) {
+        let fields: Vec<&str> = line.split(':').collect();
+        if let [name, _, id, ..] = fields.as_slice()
+            && let Ok(id) = id.parse::<u32>()
+        {
+            names.entry(id).or_insert_with(|| name.to_string());
+        }
+    }
+    names
+}
+
+/// Formats permission bits as an octal string such as `0755` or `4755`.
+pub fn octal_mode(mode: u32) -> String {
+    format!("{:04o}", mode & 0o7777)
+}
+
+/// Formats permission bits as a symbolic string such as `rwxr-sr-t`.
+///
+/// Like `ls -l`, the setuid and setgid bits replace the owner and group
+/// execute position with `s` (or `S` when not executable), and the sticky
+/// bit replaces the others execute position with `t` (or `T`).
+pub fn symbolic_mode(mode: u32) -> String {
+    let special = [(SETUID, 's'), (SETGID, 's'), (STICKY, 't')];
+    let mut symbolic = String::with_capacity(9);
+    for (class, (bit, marker)) in special.into_iter().enumerate() {
+        let bits = (mode >> (6 - 3 * class)) & 0o7;
+        symbolic.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        symbolic.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        symbolic.push(match (bits & 0o1 != 0, mode & bit != 0) {
+            (true, true) => marker,
+            (false, true) => marker.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    symbolic
+}
+
+/// Parses a permission mask in octal (`0002`, `4000`) or symbolic form
+/// (`o+w`, `u+s`, `ug+rw`, `+t`), as used by `chmod`.
+///
+/// Several symbolic clauses may be separated by commas (`u+x,g+x`). A clause
+/// without a class applies to everyone (`a`); `s` refers to the setuid bit
+/// for `u` and the setgid bit for `g`, and `t` to the sticky bit.
+///
+/// # Errors
+///
+/// Returns an error if the mask is neither valid octal nor valid symbolic notation.
+pub fn parse_mode_mask(s: &str) -> Result<u32, String> {
+    let s = s.trim();
+    if !s.is_empty() && s.chars().all(|c| c.is_digit(8)) {
+        return u32::from_str_radix(s, 8)
+            .ok()
+            .filter(|mask| *mask <= 0o7777)
+            .ok_or_else(|| format!("Invalid permission mask: {}", s));
+    }
+
+    let mut mask = 0;
+    for clause in s.split(',') {
+        let invalid = || format!("Invalid permission mask: {}", s);
+        let (classes, permissions) = clause
+            .split_once(['+', '='])
+            .ok_or_else(invalid)?;
+        let classes = if classes.is_empty() { "a" } else { classes };
+        if permissions.is_empty() {
+            return Err(invalid());
+        }
+
+        for class in classes.chars() {
+            let (shift, special) = match class {
+                'u' => (6, SETUID),
+                'g' => (3, SETGID),
+                'o' => (0, 0),
+                'a' => {
+                    mask |= parse_permissions(permissions, 6, SETUID).ok_or_else(invalid)?
+                        | parse_permissions(permissions, 3, SETGID).ok_or_else(invalid)?
+                        | parse_permissions(permissions, 0, 0).ok_or_else(invalid)?;
+                    continue;
+                }
+                _ => return Err(invalid()),
+            };
+            mask |= parse_permissions(permissions, shift, special).ok_or_else(invalid)?;
+        }
+    }
+    Ok(mask)
+}
+
+/// Converts `rwxst` letters to bits for one class of users.
+fn parse_permissions(permissions: &str, shift: u32, special: u32) -> Option<u32> {
+    permissions.chars().try_fold(0, |mask, permission| {
+        Some(mask | match permission {
+            'r' => 0o4 << shift,
+            'w' => 0o2 << shift,
+            'x' => 0o1 << shift,
+            's' => special,
+            't' => STICKY,
+            _ => return None,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_database() {
+        let names = parse_database("# comment\nroot:x:0:0:root:/root:/bin/sh\nbroken line\nalice:x:1000:1000::/home/alice:/bin/sh\nalias:x:0:0\n");
+        assert_eq!(names[&0], "root");
+        assert_eq!(names[&1000], "alice");
+        assert_eq!(names.len(), 2);
+    }
+
+    #[test]
+    fn test_modes() {
+        assert_eq!(octal_mode(0o100644), "0644");
+        assert_eq!(symbolic_mode(0o755), "rwxr-xr-x");
+        assert_eq!(symbolic_mode(0o4755), "rwsr-xr-x");
+        assert_eq!(symbolic_mode(0o2640), "rw-r-S---");
+        assert_eq!(symbolic_mode(0o1777), "rwxrwxrwt");
+    }
+
+    #[test]
+    fn test_parse_mode_mask() {
+        assert_eq!(parse_mode_mask("o+w"), Ok(0o002));
+        assert_eq!(parse_mode_mask("ug+rw"), Ok(0o660));
+        assert_eq!(parse_mode_mask("u+s"), Ok(SETUID));
+        assert_eq!(parse_mode_mask("+t"), Ok(STICKY));
+        assert_eq!(parse_mode_mask("a+x"), Ok(0o111));
+        assert_eq!(parse_mode_mask("u+x,g+x"), Ok(0o110));
+        assert_eq!(parse_mode_mask("0002"), Ok(0o002));
+        assert!(parse_mode_mask("o+q").is_err());
+        assert!(parse_mode_mask("9").is_err());
+        assert!(parse_mode_mask("x+w").is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use super::QueryError;
+
+/// The kind of a lexical unit in a query string.
+///
+/// Keywords are not distinguished at this level; the parser recognizes
+/// them by comparing `Word` tokens case-insensitively.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    /// An identifier, keyword, number or bare path (e.g. `size`, `SELECT`, `100`, `./src`)
+    Word(String),
+    /// A single- or double-quoted string literal with the quotes removed
+    Str(String),
+    /// A comparison operator (`=`, `==`, `!=`, `<>`, `<`, `<=`, `>`, `>=`, `~`)
+    Operator(String),
+    Comma,
+    LParen,
+    RParen,
+    Star,
+    Semicolon,
+    End,
+}
+
+/// A token together with the byte offset at which it starts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub position: usize,
+}
+
+impl TokenKind {
+    /// Describes the token for use in error messages.
+    pub fn describe(&self) -> String {
+        match self {
+            TokenKind::Word(word) => format!("'{}'", word),
+            TokenKind::Str(text) => format!("string '{}'", text),
+            TokenKind::Operator(operator) => format!("'{}'", operator),
+            TokenKind::Comma => "','".to_string(),
+            TokenKind::LParen => "'('".to_string(),
+            TokenKind::RParen => "')'".to_string(),
+            TokenKind::Star => "'*'".to_string(),
+            TokenKind::Semicolon => "';'".to_string(),
+            TokenKind::End => "end of query".to_string(),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '/' | '-' | ':' | '+')
+}
+
+/// Splits a query string into tokens, always terminated by `TokenKind::End`.
+///
+/// # Errors
+///
+/// Returns an error pointing at the offending character if the query
+/// contains an unterminated string or a character that cannot start a token.
+pub fn tokenize(sql: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = sql.char_indices().peekable();
+
+    while let Some(&(position, c)) = chars.peek() {
+        let kind = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            ',' | '(' | ')' | '*' | ';' | '~' => {
+                chars.next();
+                match c {
+                    ',' => TokenKind::Comma,
+                    '(' => TokenKind::LParen,
+                    ')' => TokenKind::RParen,
+                    '*' => TokenKind::Star,
+                    ';' => TokenKind::Semicolon,
+                    _ => TokenKind::Operator("~".to_string()),
+                }
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let mut operator = c.to_string();
+                if let Some(&(_, next)) = chars.peek()
+                    && matches!((c, next), ('=', '=') | ('!', '=') | ('<', '=') | ('<', '>') | ('>', '='))
+                {
+                    operator.push(next);
+                    chars.next();
+                }
+                if operator == "!" {
+                    return Err(QueryError::new("Unexpected '!', use NOT or '!='", position));
+                }
+                TokenKind::Operator(operator)
+            }
+            '\'' | '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, q)) if q == c => break,
+                        Some((_, other)) => text.push(other),
+                        None => return Err(QueryError::new("Unterminated string literal", position)),
+                    }
+                }
+                TokenKind::Str(text)
+            }
+            c if is_word_char(c) => {
+                let mut word = String::new();
+                while let Some(&(_, c)) = chars.peek() {
+                    if !is_word_char(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                TokenKind::Word(word)
+            }
+            _ => return Err(QueryError::new(format!("Unexpected character '{}'", c), position)),
+        };
+        tokens.push(Token { kind, position });
+    }
+
+    tokens.push(Token { kind: TokenKind::End, position: sql.len() });
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(sql: &str) -> Vec<TokenKind> {
+        tokenize(sql).unwrap().into_iter().map(|token| token.kind).collect()
+    }
+
+    #[test]
+    fn test_tokenize_statement() {
+        assert_eq!(
+            kinds("SELECT count(*) FROM ./src WHERE size >= 10"),
+            vec![
+                TokenKind::Word("SELECT".to_string()),
+                TokenKind::Word("count".to_string()),
+                TokenKind::LParen,
+                TokenKind::Star,
+                TokenKind::RParen,
+                TokenKind::Word("FROM".to_string()),
+                TokenKind::Word("./src".to_string()),
+                TokenKind::Word("WHERE".to_string()),
+                TokenKind::Word("size".to_string()),
+                TokenKind::Operator(">=".to_string()),
+                TokenKind::Word("10".to_string()),
+                TokenKind::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_strings_and_positions() {
+        let tokens = tokenize("name ~ 'a b' <> \"c\"").unwrap();
+        assert_eq!(tokens[2].kind, TokenKind::Str("a b".to_string()));
+        assert_eq!(tokens[2].position, 7);
+        assert_eq!(tokens[3].kind, TokenKind::Operator("<>".to_string()));
+        assert_eq!(tokens[4].kind, TokenKind::Str("c".to_string()));
+    }
+
+    #[test]
+    fn test_tokenize_errors() {
+        assert_eq!(tokenize("name = 'open").unwrap_err().position, 7);
+        assert_eq!(tokenize("size # 3").unwrap_err().position, 5);
+    }
+}
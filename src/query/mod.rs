@@ -0,0 +1,183 @@
+//! A SQL-like front end for lsdir.
+//!
+//! A query string such as
+//! `SELECT extension, count(*) FROM . WHERE size > 100 GROUP BY extension ORDER BY count(*) DESC LIMIT 5`
+//! is tokenized by the `lexer`, turned into a syntax tree by the `parser` and
+//! compiled by the `planner` into a `Query`, which uses the same filtering,
+//! grouping and aggregation machinery as the command line flags.
+
+mod lexer;
+mod parser;
+mod planner;
+
+use crate::utilities::aggregate::{AggregateFunction, Having};
+use crate::utilities::field::Field;
+use crate::utilities::filter::Expr;
+use crate::utilities::group::GroupingOperator;
+use crate::utilities::order::OrderKey;
+
+use std::fmt::Display;
+
+/// An executable description of what to list or compute.
+///
+/// # Fields
+/// - `path`: The directory to analyze, if the query names one
+/// - `filter`: The WHERE expression files must satisfy
+/// - `group_by`: How to group the remaining files, outermost key first
+/// - `having`: The HAVING expression groups must satisfy
+/// - `aggregates`: The aggregates to compute per group instead of listing files
+/// - `columns`: The fields to show when listing files
+/// - `order_by`: Sort keys for files and groups, in priority order
+/// - `limit`: The maximum number of rows to show (files per group, or groups when aggregating)
+/// - `offset`: The number of rows to skip before applying `limit`
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    pub path: Option<String>,
+    pub filter: Option<Expr>,
+    pub group_by: Vec<GroupingOperator>,
+    pub having: Option<Having>,
+    pub aggregates: Vec<AggregateFunction>,
+    pub columns: Vec<Field>,
+    pub order_by: Vec<OrderKey>,
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+/// An error in a query string, located at a byte offset within it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl QueryError {
+    pub fn new(message: impl Into<String>, position: usize) -> Self {
+        Self { message: message.into(), position }
+    }
+
+    /// Formats the error together with the query and a caret under the offending token.
+    pub fn render(&self, sql: &str) -> String {
+        let line = sql.replace(['\n', '\r', '\t'], " ");
+        let column = sql[..self.position.min(sql.len())].chars().count();
+        format!("{}\n  {}\n  {}^", self, line, " ".repeat(column))
+    }
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (at position {})", self.message, self.position)
+    }
+}
+
+/// Parses and plans a query string.
+///
+/// # Errors
+///
+/// Returns a `QueryError` locating the first token that could not be
+/// tokenized, parsed or resolved.
+pub fn parse(sql: &str) -> Result<Query, QueryError> {
+    let tokens = lexer::tokenize(sql)?;
+    let statement = parser::Parser::new(tokens).parse_statement()?;
+    planner::plan(statement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::aggregate::ArithmeticAggregator;
+    use crate::utilities::order::OrderTarget;
+
+    #[test]
+    fn test_plan_aggregate_query() {
+        let query = parse(
+            "SELECT extension, sum(size) FROM ./src WHERE size > 10 GROUP BY ext ORDER BY sum(size) DESC LIMIT 3",
+        )
+        .unwrap();
+        assert_eq!(query.path.as_deref(), Some("./src"));
+        assert!(query.filter.is_some());
+        assert!(matches!(query.group_by[..], [GroupingOperator::Extension]));
+        assert!(matches!(query.aggregates[..], [AggregateFunction::Sum(_)]));
+        assert!(query.columns.is_empty());
+        assert!(matches!(query.order_by[0].target, OrderTarget::Aggregate(AggregateFunction::Sum(_))));
+        assert!(query.order_by[0].descending);
+        assert_eq!(query.limit, Some(3));
+    }
+
+    #[test]
+    fn test_plan_several_group_keys() {
+        let query = parse("SELECT ext, modified, count(*) GROUP BY ext, modified(year, month)").unwrap();
+        assert!(matches!(query.group_by[..], [GroupingOperator::Extension, GroupingOperator::Modified(_)]));
+        assert!(parse("SELECT name, count(*) GROUP BY ext, modified(year)").is_err());
+    }
+
+    #[test]
+    fn test_plan_having() {
+        let query = parse("SELECT ext, count(*) GROUP BY ext HAVING count(*) > 100 OR max(modified) > 12:00").unwrap();
+        assert!(matches!(query.having, Some(Having::Or(_, _))));
+        let error = parse("SELECT ext, count(*) GROUP BY ext HAVING size > 100").unwrap_err();
+        assert_eq!(error.position, 41);
+    }
+
+    #[test]
+    fn test_plan_multiple_aggregates() {
+        let query = parse("SELECT ext, count(*), sum(size), max(modified) GROUP BY ext").unwrap();
+        assert!(matches!(
+            query.aggregates[..],
+            [AggregateFunction::Count, AggregateFunction::Sum(_), AggregateFunction::Max(_)]
+        ));
+    }
+
+    #[test]
+    fn test_plan_histogram() {
+        let query = parse("SELECT ext, histogram(size, buckets, 0, 4K, 1M), median(size) GROUP BY ext").unwrap();
+        assert!(matches!(
+            query.aggregates[..],
+            [AggregateFunction::Histogram(ArithmeticAggregator::Size, _), AggregateFunction::Median(_)]
+        ));
+        let error = parse("SELECT ext, histogram(modified, month) GROUP BY ext ORDER BY histogram(modified, month)").unwrap_err();
+        assert_eq!(error.position, 61);
+        assert!(parse("SELECT histogram(name) GROUP BY ext").is_err());
+    }
+
+    #[test]
+    fn test_plan_listing_query() {
+        let query = parse("SELECT name, size WHERE NOT ext = rs ORDER BY size DESC, name").unwrap();
+        assert_eq!(query.columns, vec![Field::Name, Field::Size]);
+        assert!(matches!(query.filter, Some(Expr::Not(_))));
+        assert_eq!(query.order_by.len(), 2);
+        assert_eq!(query.offset, 0);
+
+        let query = parse("SELECT *").unwrap();
+        assert_eq!(query.columns.len(), 6);
+    }
+
+    #[test]
+    fn test_plan_errors_point_at_item() {
+        let sql = "SELECT name, colour FROM .";
+        assert_eq!(parse(sql).unwrap_err().position, 13);
+
+        let sql = "SELECT count(*) WHERE size > big";
+        let error = parse(sql).unwrap_err();
+        assert_eq!(error.position, 22);
+        assert!(error.message.contains("Invalid size value"));
+
+        let sql = "SELECT name, count(*) GROUP BY extension";
+        assert_eq!(parse(sql).unwrap_err().position, 7);
+
+        assert!(parse("SELECT count(size) GROUP BY extension").unwrap_err().message.contains("count takes no field"));
+        let sql = "SELECT count(*), sum(size), max(colour) GROUP BY extension";
+        assert_eq!(parse(sql).unwrap_err().position, 28);
+
+        let sql = "SELECT name ORDER BY count(*)";
+        assert_eq!(parse(sql).unwrap_err().position, 21);
+    }
+
+    #[test]
+    fn test_render_error() {
+        let error = QueryError::new("Expected a value", 7);
+        assert_eq!(
+            error.render("SELECT FROM"),
+            "Expected a value (at position 7)\n  SELECT FROM\n         ^"
+        );
+    }
+}
@@ -0,0 +1,445 @@
+use super::QueryError;
+use super::lexer::{Token, TokenKind};
+
+/// A parsed `SELECT` statement, before any names are resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub select: Vec<SelectItem>,
+    pub from: Option<String>,
+    pub where_clause: Option<Condition>,
+    pub group_by: Vec<GroupBy>,
+    pub having: Option<Condition>,
+    pub order_by: Vec<OrderItem>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+/// An entry of the SELECT list.
+///
+/// # Variants
+/// - `All`: `*`
+/// - `Column`: A plain field name
+/// - `Aggregate`: A function call such as `count(*)` or `max(size)`
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectItem {
+    All,
+    Column { name: String, position: usize },
+    Aggregate { function: String, argument: Option<String>, position: usize },
+}
+
+/// A WHERE or HAVING condition tree.
+///
+/// The field of a comparison is a plain name in WHERE and an aggregate call
+/// rendered as text (e.g. `sum(size)`, `count(*)`) in HAVING.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Compare { field: String, operator: String, value: String, position: usize },
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+/// A GROUP BY key: a field with optional arguments, e.g. `modified(year, month)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupBy {
+    pub field: String,
+    pub arguments: Vec<String>,
+    pub position: usize,
+}
+
+/// An ORDER BY entry: a column or aggregate with its direction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderItem {
+    pub target: SelectItem,
+    pub descending: bool,
+}
+
+/// Recursive descent parser over the tokens of a query.
+pub struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Self { tokens, position: 0 }
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.position].clone();
+        if token.kind != TokenKind::End {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn unexpected(&self, expected: &str) -> QueryError {
+        let token = self.peek();
+        QueryError::new(
+            format!("Expected {}, found {}", expected, token.kind.describe()),
+            token.position,
+        )
+    }
+
+    /// Whether the next token is the given keyword (case-insensitive).
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.peek().kind, TokenKind::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.at_keyword(keyword) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), QueryError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(keyword))
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<(), QueryError> {
+        if self.peek().kind == kind {
+            self.advance();
+            Ok(())
+        } else {
+            Err(self.unexpected(&kind.describe()))
+        }
+    }
+
+    fn at_clause_keyword(&self) -> bool {
+        ["from", "where", "group", "having", "order", "limit", "offset"]
+            .iter()
+            .any(|keyword| self.at_keyword(keyword))
+    }
+
+    /// Consumes a word that is not a clause keyword, returning it and its position.
+    fn identifier(&mut self, expected: &str) -> Result<(String, usize), QueryError> {
+        match &self.peek().kind {
+            TokenKind::Word(word) if !self.at_clause_keyword() => {
+                let word = word.clone();
+                Ok((word, self.advance().position))
+            }
+            _ => Err(self.unexpected(expected)),
+        }
+    }
+
+    /// Consumes a word or string literal used as a value.
+    fn literal(&mut self, expected: &str) -> Result<String, QueryError> {
+        match &self.peek().kind {
+            TokenKind::Word(text) | TokenKind::Str(text) => {
+                let text = text.clone();
+                self.advance();
+                Ok(text)
+            }
+            _ => Err(self.unexpected(expected)),
+        }
+    }
+
+    fn number(&mut self, expected: &str) -> Result<usize, QueryError> {
+        let token = self.peek().clone();
+        match &token.kind {
+            TokenKind::Word(word) => {
+                let number = word
+                    .parse::<usize>()
+                    .map_err(|_| QueryError::new(format!("Expected {}, found '{}'", expected, word), token.position))?;
+                self.advance();
+                Ok(number)
+            }
+            _ => Err(self.unexpected(expected)),
+        }
+    }
+
+    /// Parses a complete statement, requiring that all input is consumed.
+    pub fn parse_statement(&mut self) -> Result<Statement, QueryError> {
+        self.expect_keyword("select")?;
+        let select = self.parse_select_list()?;
+
+        let from = if self.eat_keyword("from") {
+            Some(self.literal("a directory path")?)
+        } else {
+            None
+        };
+
+        let where_clause = if self.eat_keyword("where") {
+            Some(self.parse_or()?)
+        } else {
+            None
+        };
+
+        let mut group_by = Vec::new();
+        if self.eat_keyword("group") {
+            self.expect_keyword("by")?;
+            loop {
+                group_by.push(self.parse_group_by()?);
+                if self.peek().kind != TokenKind::Comma {
+                    break;
+                }
+                self.advance();
+            }
+        }
+
+        let having = if self.eat_keyword("having") {
+            Some(self.parse_or()?)
+        } else {
+            None
+        };
+
+        let mut order_by = Vec::new();
+        if self.eat_keyword("order") {
+            self.expect_keyword("by")?;
+            loop {
+                order_by.push(self.parse_order_item()?);
+                if self.peek().kind != TokenKind::Comma {
+                    break;
+                }
+                self.advance();
+            }
+        }
+
+        let limit = if self.eat_keyword("limit") {
+            Some(self.number("a row count after LIMIT")?)
+        } else {
+            None
+        };
+
+        let offset = if self.eat_keyword("offset") {
+            Some(self.number("a row count after OFFSET")?)
+        } else {
+            None
+        };
+
+        if self.peek().kind == TokenKind::Semicolon {
+            self.advance();
+        }
+        if self.peek().kind != TokenKind::End {
+            return Err(self.unexpected("end of query"));
+        }
+
+        Ok(Statement { select, from, where_clause, group_by, having, order_by, limit, offset })
+    }
+
+    fn parse_select_list(&mut self) -> Result<Vec<SelectItem>, QueryError> {
+        let mut items = Vec::new();
+        loop {
+            if self.peek().kind == TokenKind::Star {
+                self.advance();
+                items.push(SelectItem::All);
+            } else {
+                items.push(self.parse_item("a column, aggregate or '*'")?);
+            }
+            if self.peek().kind != TokenKind::Comma {
+                return Ok(items);
+            }
+            self.advance();
+        }
+    }
+
+    /// Parses a column name or an aggregate call such as `sum(size)` or
+    /// `histogram(size, log2)`, whose arguments are joined with commas.
+    fn parse_item(&mut self, expected: &str) -> Result<SelectItem, QueryError> {
+        let (name, position) = self.identifier(expected)?;
+        if self.peek().kind != TokenKind::LParen {
+            return Ok(SelectItem::Column { name, position });
+        }
+        self.advance();
+        let argument = match &self.peek().kind {
+            TokenKind::Star => {
+                self.advance();
+                None
+            }
+            TokenKind::RParen => None,
+            _ => {
+                let mut arguments = vec![self.identifier("a field name")?.0];
+                while self.peek().kind == TokenKind::Comma {
+                    self.advance();
+                    arguments.push(self.identifier("an aggregate argument")?.0);
+                }
+                Some(arguments.join(","))
+            }
+        };
+        self.expect(TokenKind::RParen)?;
+        Ok(SelectItem::Aggregate { function: name, argument, position })
+    }
+
+    fn parse_or(&mut self) -> Result<Condition, QueryError> {
+        let mut condition = self.parse_and()?;
+        while self.eat_keyword("or") {
+            condition = Condition::Or(Box::new(condition), Box::new(self.parse_and()?));
+        }
+        Ok(condition)
+    }
+
+    fn parse_and(&mut self) -> Result<Condition, QueryError> {
+        let mut condition = self.parse_not()?;
+        while self.eat_keyword("and") {
+            condition = Condition::And(Box::new(condition), Box::new(self.parse_not()?));
+        }
+        Ok(condition)
+    }
+
+    fn parse_not(&mut self) -> Result<Condition, QueryError> {
+        if self.eat_keyword("not") {
+            return Ok(Condition::Not(Box::new(self.parse_not()?)));
+        }
+        if self.peek().kind == TokenKind::LParen {
+            self.advance();
+            let condition = self.parse_or()?;
+            self.expect(TokenKind::RParen)?;
+            return Ok(condition);
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Condition, QueryError> {
+        let (field, position) = match self.parse_item("a condition")? {
+            SelectItem::Column { name, position } => (name, position),
+            SelectItem::Aggregate { function, argument, position } => {
+                (format!("{}({})", function, argument.as_deref().unwrap_or("*")), position)
+            }
+            SelectItem::All => unreachable!("conditions never parse '*'"),
+        };
+        let operator = match &self.peek().kind {
+            TokenKind::Operator(operator) => operator.clone(),
+            TokenKind::Word(word) if word.eq_ignore_ascii_case("like") => "~".to_string(),
+            TokenKind::Word(word) if word.eq_ignore_ascii_case("has") => "has".to_string(),
+            _ => return Err(self.unexpected("a comparison operator")),
+        };
+        self.advance();
+        let value = self.literal("a value")?;
+        Ok(Condition::Compare { field, operator, value, position })
+    }
+
+    fn parse_group_by(&mut self) -> Result<GroupBy, QueryError> {
+        let (field, position) = self.identifier("a field to group by")?;
+        let mut arguments = Vec::new();
+        if self.peek().kind == TokenKind::LParen {
+            self.advance();
+            loop {
+                arguments.push(self.identifier("a grouping argument")?.0);
+                if self.peek().kind != TokenKind::Comma {
+                    break;
+                }
+                self.advance();
+            }
+            self.expect(TokenKind::RParen)?;
+        }
+        Ok(GroupBy { field, arguments, position })
+    }
+
+    fn parse_order_item(&mut self) -> Result<OrderItem, QueryError> {
+        let target = self.parse_item("a column or aggregate to order by")?;
+        let descending = if self.eat_keyword("desc") {
+            true
+        } else {
+            self.eat_keyword("asc");
+            false
+        };
+        Ok(OrderItem { target, descending })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::lexer::tokenize;
+
+    fn parse(sql: &str) -> Result<Statement, QueryError> {
+        Parser::new(tokenize(sql)?).parse_statement()
+    }
+
+    #[test]
+    fn test_parse_full_statement() {
+        let statement = parse(
+            "SELECT extension, sum(size) FROM 'my dir' WHERE size > 10 AND NOT (ext = log OR name ~ tmp) \
+             GROUP BY extension ORDER BY sum(size) DESC, extension LIMIT 5 OFFSET 2;",
+        )
+        .unwrap();
+        assert_eq!(statement.select.len(), 2);
+        assert_eq!(
+            statement.select[1],
+            SelectItem::Aggregate { function: "sum".to_string(), argument: Some("size".to_string()), position: 18 }
+        );
+        assert_eq!(statement.from.as_deref(), Some("my dir"));
+        assert!(matches!(statement.where_clause, Some(Condition::And(_, ref right)) if matches!(**right, Condition::Not(_))));
+        assert_eq!(statement.group_by.len(), 1);
+        assert_eq!(statement.group_by[0].field, "extension");
+        assert_eq!(statement.order_by.len(), 2);
+        assert!(statement.order_by[0].descending);
+        assert!(!statement.order_by[1].descending);
+        assert_eq!(statement.limit, Some(5));
+        assert_eq!(statement.offset, Some(2));
+    }
+
+    #[test]
+    fn test_parse_minimal_statement() {
+        let statement = parse("select *").unwrap();
+        assert_eq!(statement.select, vec![SelectItem::All]);
+        assert_eq!(statement.from, None);
+        assert_eq!(statement.where_clause, None);
+    }
+
+    #[test]
+    fn test_parse_group_by_arguments() {
+        let statement = parse("SELECT count(*) GROUP BY modified(year, month)").unwrap();
+        let group_by = &statement.group_by[0];
+        assert_eq!(group_by.field, "modified");
+        assert_eq!(group_by.arguments, vec!["year", "month"]);
+
+        let statement = parse("SELECT histogram(size, linear, 1M) GROUP BY ext").unwrap();
+        assert_eq!(
+            statement.select[0],
+            SelectItem::Aggregate {
+                function: "histogram".to_string(),
+                argument: Some("size,linear,1M".to_string()),
+                position: 7
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_group_by_several_keys() {
+        let statement = parse("SELECT count(*) GROUP BY ext, modified(year, month) ORDER BY ext").unwrap();
+        assert_eq!(statement.group_by.len(), 2);
+        assert_eq!(statement.group_by[0].field, "ext");
+        assert_eq!(statement.group_by[1].field, "modified");
+        assert_eq!(statement.group_by[1].arguments, vec!["year", "month"]);
+        assert_eq!(statement.order_by.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_having() {
+        let statement = parse("SELECT ext, count(*) GROUP BY ext HAVING count(*) > 100 AND sum(size) >= 10 LIMIT 3").unwrap();
+        let Some(Condition::And(left, right)) = statement.having else {
+            panic!("expected a conjunction");
+        };
+        assert!(matches!(*left, Condition::Compare { ref field, ref value, .. } if field == "count(*)" && value == "100"));
+        assert!(matches!(*right, Condition::Compare { ref field, .. } if field == "sum(size)"));
+        assert_eq!(statement.limit, Some(3));
+    }
+
+    #[test]
+    fn test_parse_errors_point_at_token() {
+        let error = parse("SELECT name FROM . WHERE size 10").unwrap_err();
+        assert_eq!(error.position, 30);
+        assert!(error.message.contains("comparison operator"));
+
+        let error = parse("SELECT name FROM . LIMIT ten").unwrap_err();
+        assert_eq!(error.position, 25);
+
+        let error = parse("SELECT FROM .").unwrap_err();
+        assert_eq!(error.position, 7);
+
+        let error = parse("SELECT name FROM . extra").unwrap_err();
+        assert_eq!(error.position, 19);
+    }
+}
@@ -0,0 +1,141 @@
+use super::parser::{Condition, GroupBy, SelectItem, Statement};
+use super::{Query, QueryError};
+use crate::utilities::aggregate::{AggregateFunction, GroupPredicate, Having};
+use crate::utilities::field::{Field, DEFAULT_COLUMNS};
+use crate::utilities::filter::{Expr, Predicate};
+use crate::utilities::group::GroupingOperator;
+use crate::utilities::order::{OrderKey, OrderTarget};
+
+use std::str::FromStr;
+
+/// Compiles a parsed statement into an executable `Query`.
+///
+/// Names in the statement are resolved with the same parsers the command
+/// line flags use, so every alias accepted by `--where`, `--group-by` and
+/// `--aggregate` works in queries too.
+///
+/// # Errors
+///
+/// Returns an error pointing at the offending item if a name cannot be
+/// resolved or the statement combines clauses in an unsupported way.
+pub fn plan(statement: Statement) -> Result<Query, QueryError> {
+    let group_by = statement.group_by
+        .into_iter()
+        .map(compile_group_by)
+        .collect::<Result<Vec<_>, _>>()?;
+    let group_fields: Vec<Field> = group_by.iter().map(GroupingOperator::field).collect();
+
+    let mut columns = Vec::new();
+    let mut aggregates = Vec::new();
+    for item in &statement.select {
+        match item {
+            SelectItem::All => columns.extend(DEFAULT_COLUMNS),
+            SelectItem::Column { name, position } => columns.push(compile_field(name, *position)?),
+            SelectItem::Aggregate { .. } => aggregates.push(compile_aggregate(item)?),
+        }
+    }
+
+    if !aggregates.is_empty() {
+        // Plain columns next to an aggregate can only repeat a group key
+        for item in &statement.select {
+            match item {
+                SelectItem::All => {
+                    return Err(QueryError::new("'*' cannot be combined with an aggregate", 0));
+                }
+                SelectItem::Column { name, position } if !group_fields.contains(&compile_field(name, *position)?) => {
+                    return Err(QueryError::new(
+                        format!("Column '{}' must appear in GROUP BY or be used in an aggregate", name),
+                        *position,
+                    ));
+                }
+                _ => {}
+            }
+        }
+        columns.clear();
+    }
+
+    let mut order_by = Vec::new();
+    for item in statement.order_by {
+        let target = match &item.target {
+            SelectItem::Column { name, position } => OrderTarget::Field(compile_field(name, *position)?),
+            SelectItem::Aggregate { position, .. } => {
+                if group_by.is_empty() && aggregates.is_empty() {
+                    return Err(QueryError::new("ORDER BY an aggregate requires GROUP BY", *position));
+                }
+                match compile_aggregate(&item.target)? {
+                    AggregateFunction::Histogram(..) => {
+                        return Err(QueryError::new("Cannot ORDER BY a histogram", *position));
+                    }
+                    aggregate => OrderTarget::Aggregate(aggregate),
+                }
+            }
+            SelectItem::All => unreachable!("ORDER BY never parses '*'"),
+        };
+        order_by.push(OrderKey { target, descending: item.descending });
+    }
+
+    Ok(Query {
+        path: statement.from,
+        filter: statement.where_clause.map(compile_condition).transpose()?,
+        group_by,
+        having: statement.having.map(compile_having).transpose()?,
+        aggregates,
+        columns,
+        order_by,
+        limit: statement.limit,
+        offset: statement.offset.unwrap_or(0),
+    })
+}
+
+fn compile_field(name: &str, position: usize) -> Result<Field, QueryError> {
+    Field::from_str(name).map_err(|e| QueryError::new(e, position))
+}
+
+fn compile_aggregate(item: &SelectItem) -> Result<AggregateFunction, QueryError> {
+    let SelectItem::Aggregate { function, argument, position } = item else {
+        unreachable!("only aggregate items are compiled as aggregates");
+    };
+    let spec = match argument {
+        Some(argument) => format!("{},{}", function, argument),
+        None => function.clone(),
+    };
+    AggregateFunction::from_str(&spec).map_err(|e| QueryError::new(e, *position))
+}
+
+fn compile_group_by(group_by: GroupBy) -> Result<GroupingOperator, QueryError> {
+    let spec = std::iter::once(group_by.field)
+        .chain(group_by.arguments)
+        .collect::<Vec<_>>()
+        .join(",");
+    GroupingOperator::from_str(&spec).map_err(|e| QueryError::new(e, group_by.position))
+}
+
+fn compile_condition(condition: Condition) -> Result<Expr, QueryError> {
+    Ok(match condition {
+        Condition::Compare { field, operator, value, position } => Expr::Predicate(
+            Predicate::from_parts(&field, &operator, &value).map_err(|e| QueryError::new(e, position))?,
+        ),
+        Condition::Not(inner) => Expr::Not(Box::new(compile_condition(*inner)?)),
+        Condition::And(left, right) => {
+            Expr::and(compile_condition(*left)?, compile_condition(*right)?)
+        }
+        Condition::Or(left, right) => {
+            Expr::or(compile_condition(*left)?, compile_condition(*right)?)
+        }
+    })
+}
+
+fn compile_having(condition: Condition) -> Result<Having, QueryError> {
+    Ok(match condition {
+        Condition::Compare { field, operator, value, position } => Having::Predicate(
+            GroupPredicate::from_parts(&field, &operator, &value).map_err(|e| QueryError::new(e, position))?,
+        ),
+        Condition::Not(inner) => Having::Not(Box::new(compile_having(*inner)?)),
+        Condition::And(left, right) => {
+            Having::and(compile_having(*left)?, compile_having(*right)?)
+        }
+        Condition::Or(left, right) => {
+            Having::or(compile_having(*left)?, compile_having(*right)?)
+        }
+    })
+}
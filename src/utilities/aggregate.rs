@@ -1,12 +1,24 @@
 use crate::file::File;
+use crate::utilities::field::{Field, Value};
+use crate::utilities::filter::{BoolExpr, Comparison, Leaf};
+use crate::utilities::group::{format_bound, SizeGrouping, SizeMagnitude, TimeGrouping};
+use crate::utilities::size::parse_size;
+use crate::utilities::time::{parse_duration, parse_time, TimeRange};
 
-use std::collections::HashMap; 
+use chrono::{DateTime, Local};
+
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display; 
 use std::str::FromStr;
 
 
 
 /// Represents an aggregate function that can be applied to a collection of files.
+///
+/// `Percentile` holds the rank between 0 and 100, e.g. 95 for `p95`.
+/// `Histogram` counts the files of a group per bucket of a numeric or time
+/// field (see `histogram`); it yields several values per group, so it cannot
+/// be compared in HAVING or ORDER BY.
 #[derive(Debug, Clone)]
 pub enum AggregateFunction {
     Count,
@@ -14,6 +26,12 @@ pub enum AggregateFunction {
     Avg(ArithmeticAggregator),
     Max(ComparingAggregator),
     Min(ComparingAggregator),
+    Median(ArithmeticAggregator),
+    Percentile(f64, ArithmeticAggregator),
+    StdDev(ArithmeticAggregator),
+    Variance(ArithmeticAggregator),
+    Mode(ArithmeticAggregator),
+    Histogram(ArithmeticAggregator, HistogramBuckets),
 }
 
 impl FromStr for AggregateFunction {
@@ -21,34 +39,438 @@ impl FromStr for AggregateFunction {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let parts: Vec<&str> = s.splitn(2, ',').collect();
+        let argument = |function: &str| {
+            parts.get(1).map_or(Ok(ArithmeticAggregator::Size), |part| parse_argument(function, part))
+        };
         match parts[0].to_lowercase().as_str() {
-            "count" | "c" => Ok(AggregateFunction::Count),
+            "count" | "c" => match parts.get(1).map(|argument| argument.trim()) {
+                None | Some("*") => Ok(AggregateFunction::Count),
+                Some(argument) => Err(format!("count takes no field, as it counts every file: {}", argument)),
+            },
             "sum" | "s" => {
-                Ok(AggregateFunction::Sum(ArithmeticAggregator::Size))
-            }
-            "average" | "avg" | "a" => {
-                Ok(AggregateFunction::Avg(ArithmeticAggregator::Size))
+                let aggregator = argument("sum")?;
+                if aggregator.is_temporal() {
+                    return Err(format!(
+                        "Cannot compute sum of {}: timestamps can only be averaged or compared (avg, max, min)",
+                        aggregator.field().key()
+                    ));
+                }
+                Ok(AggregateFunction::Sum(aggregator))
             }
+            "average" | "avg" | "a" => Ok(AggregateFunction::Avg(argument("avg")?)),
             "max" => {
                 if parts.len() < 2 {
                     return Err("Missing argument for max".to_string());
                 }
-                let aggregator = ComparingAggregator::from_str(parts[1])?;
+                let aggregator = parse_argument("max", parts[1])?;
                 Ok(AggregateFunction::Max(aggregator))
             }
             "min" => {
                 if parts.len() < 2 {
                     return Err("Missing argument for min".to_string());
                 }
-                let aggregator = ComparingAggregator::from_str(parts[1])?;
+                let aggregator = parse_argument("min", parts[1])?;
                 Ok(AggregateFunction::Min(aggregator))
             }
-            _ => Err(format!("Unknown aggregate function: {}", s)),
+            "median" | "med" => Ok(AggregateFunction::Median(argument("median")?)),
+            "stddev" | "std" | "sd" => Ok(AggregateFunction::StdDev(argument("stddev")?)),
+            "variance" | "var" => Ok(AggregateFunction::Variance(argument("variance")?)),
+            "mode" => Ok(AggregateFunction::Mode(argument("mode")?)),
+            "histogram" | "hist" => {
+                let arguments: Vec<String> = parts
+                    .get(1)
+                    .map_or(Vec::new(), |spec| spec.split(',').map(|part| part.trim().to_lowercase()).collect());
+                let aggregator = match arguments.first() {
+                    Some(field) => parse_argument("histogram", field)?,
+                    None => ArithmeticAggregator::Size,
+                };
+                let buckets = HistogramBuckets::parse(&aggregator, arguments.get(1..).unwrap_or_default())?;
+                Ok(AggregateFunction::Histogram(aggregator, buckets))
+            }
+            function => match function.strip_prefix('p').and_then(|rank| rank.parse::<f64>().ok()) {
+                Some(rank) if (0.0..=100.0).contains(&rank) => {
+                    Ok(AggregateFunction::Percentile(rank, argument(function)?))
+                }
+                Some(_) => Err(format!("Percentile must be between 0 and 100: {}", parts[0])),
+                None => Err(format!("Unknown aggregate function: {}", s)),
+            },
         }
     }
     
 }
 
+/// Parses the field argument of an aggregate function.
+///
+/// # Errors
+///
+/// Returns an error naming the function if the field exists but the function
+/// does not apply to it (e.g. `sum,name`), or if the field is unknown.
+fn parse_argument<T: FromStr<Err = String>>(function: &str, argument: &str) -> Result<T, String> {
+    T::from_str(argument).map_err(|_| match Field::from_str(argument) {
+        Ok(field) => format!(
+            "Cannot compute {} of {}: {} needs a numeric or time field such as size, disk_usage, age, lines or modified",
+            function,
+            field.key(),
+            function
+        ),
+        Err(_) => format!("Unknown field in {}: {}", function, argument.trim()),
+    })
+}
+
+impl AggregateFunction {
+    /// Computes the aggregate over a single group of files.
+    ///
+    /// Aggregates over `disk_usage` count hard links to the same file only
+    /// once, like `du` (see `ArithmeticAggregator::counted`). Averages,
+    /// percentiles and modes of a time field are times, while its standard
+    /// deviation and variance are in seconds and seconds squared. `Max` and
+    /// `Min` yield the compared attribute of the selected file rather than
+    /// the file itself, so the result can be ordered against other groups.
+    /// Files without the attribute, such as binary files for line counts, are
+    /// left out. An empty group yields zero for `Count`, `Sum`, `Avg` and the
+    /// statistics, and an empty text value for `Max`, `Min` and `Mode`.
+    /// `Histogram` yields its buckets as text, e.g. `0–4K: 3, 4K–8K: 1`.
+    pub fn evaluate(&self, files: &[&File]) -> Value {
+        match self {
+            AggregateFunction::Count => Value::Integer(files.len() as u64),
+            AggregateFunction::Sum(aggregator) => {
+                let sum = aggregator
+                    .counted(files)
+                    .iter()
+                    .filter_map(|file| aggregator.extract(file))
+                    .fold(0i64, i64::saturating_add);
+                // Sums are never negative for the fields that can be summed; clamp rather than wrap
+                Value::Integer(u64::try_from(sum).unwrap_or(0))
+            }
+            AggregateFunction::Avg(aggregator) => {
+                let values: Vec<i64> = aggregator.counted(files).iter().filter_map(|file| aggregator.extract(file)).collect();
+                if values.is_empty() {
+                    return Value::Float(0.0);
+                }
+                aggregator.to_value(values.iter().map(|value| *value as f64).sum::<f64>() / values.len() as f64)
+            }
+            AggregateFunction::Median(aggregator) => {
+                percentile(&sorted_values(files, aggregator), 50.0).map_or(Value::Float(0.0), |median| aggregator.to_value(median))
+            }
+            AggregateFunction::Percentile(rank, aggregator) => {
+                percentile(&sorted_values(files, aggregator), *rank).map_or(Value::Float(0.0), |value| aggregator.to_value(value))
+            }
+            AggregateFunction::StdDev(aggregator) => {
+                Value::Float(variance(&sorted_values(files, aggregator)).map_or(0.0, f64::sqrt))
+            }
+            AggregateFunction::Variance(aggregator) => {
+                Value::Float(variance(&sorted_values(files, aggregator)).unwrap_or(0.0))
+            }
+            AggregateFunction::Mode(aggregator) => match mode(&sorted_values(files, aggregator)) {
+                Some(value) if aggregator.is_temporal() => aggregator.to_value(value as f64),
+                Some(value) => Value::Integer(value as u64),
+                None => Value::Text(String::new()),
+            },
+            AggregateFunction::Histogram(aggregator, buckets) => Value::Text(
+                histogram(files, aggregator, buckets)
+                    .iter()
+                    .map(|bucket| format!("{}: {}", bucket.label, bucket.count))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            AggregateFunction::Max(aggregator) | AggregateFunction::Min(aggregator) => self
+                .select(files)
+                .map_or(Value::Text(String::new()), |file| aggregator.field().value(file)),
+        }
+    }
+}
+
+impl AggregateFunction {
+    /// Finds the file whose attribute a `Max` or `Min` aggregate yields.
+    ///
+    /// Returns `None` for other aggregates and for groups without any file
+    /// having the compared attribute.
+    pub fn select<'a>(&self, files: &[&'a File]) -> Option<&'a File> {
+        let (aggregator, greatest) = match self {
+            AggregateFunction::Max(aggregator) => (aggregator, true),
+            AggregateFunction::Min(aggregator) => (aggregator, false),
+            _ => return None,
+        };
+        let candidates = files.iter().copied().filter(|file| aggregator.applies(file));
+        if greatest {
+            candidates.max_by(|a, b| aggregator.compare(a, b))
+        } else {
+            candidates.min_by(|a, b| aggregator.compare(a, b))
+        }
+    }
+
+    /// The stable machine-readable name of this aggregate, used as the key in
+    /// structured output formats (e.g. `count`, `sum_size`, `max_modified`).
+    pub fn key(&self) -> String {
+        match self {
+            AggregateFunction::Count => "count".to_string(),
+            AggregateFunction::Sum(aggregator) => format!("sum_{}", aggregator.field().key()),
+            AggregateFunction::Avg(aggregator) => format!("avg_{}", aggregator.field().key()),
+            AggregateFunction::Max(aggregator) => format!("max_{}", aggregator.field().key()),
+            AggregateFunction::Min(aggregator) => format!("min_{}", aggregator.field().key()),
+            AggregateFunction::Median(aggregator) => format!("median_{}", aggregator.field().key()),
+            AggregateFunction::Percentile(rank, aggregator) => format!("p{}_{}", rank, aggregator.field().key()),
+            AggregateFunction::StdDev(aggregator) => format!("stddev_{}", aggregator.field().key()),
+            AggregateFunction::Variance(aggregator) => format!("variance_{}", aggregator.field().key()),
+            AggregateFunction::Mode(aggregator) => format!("mode_{}", aggregator.field().key()),
+            AggregateFunction::Histogram(aggregator, _) => format!("histogram_{}", aggregator.field().key()),
+        }
+    }
+
+    /// The file field this aggregate is computed over, or `None` for `Count`.
+    pub fn field(&self) -> Option<Field> {
+        match self {
+            AggregateFunction::Count => None,
+            AggregateFunction::Sum(aggregator)
+            | AggregateFunction::Avg(aggregator)
+            | AggregateFunction::Median(aggregator)
+            | AggregateFunction::Percentile(_, aggregator)
+            | AggregateFunction::StdDev(aggregator)
+            | AggregateFunction::Variance(aggregator)
+            | AggregateFunction::Mode(aggregator)
+            | AggregateFunction::Histogram(aggregator, _) => Some(aggregator.field()),
+            AggregateFunction::Max(aggregator) | AggregateFunction::Min(aggregator) => Some(aggregator.field()),
+        }
+    }
+
+    /// Whether the values of this aggregate are numbers, which tables
+    /// right-align; aggregates yielding times or text, such as the mean of
+    /// a time or the maximum of a name, are not.
+    pub fn is_numeric(&self) -> bool {
+        match self {
+            AggregateFunction::Count | AggregateFunction::StdDev(_) | AggregateFunction::Variance(_) => true,
+            AggregateFunction::Histogram(..) => true,
+            AggregateFunction::Sum(aggregator)
+            | AggregateFunction::Avg(aggregator)
+            | AggregateFunction::Median(aggregator)
+            | AggregateFunction::Percentile(_, aggregator)
+            | AggregateFunction::Mode(aggregator) => !aggregator.is_temporal(),
+            AggregateFunction::Max(aggregator) | AggregateFunction::Min(aggregator) => aggregator.field().is_numeric(),
+        }
+    }
+
+    /// Whether the values of this aggregate are amounts of bytes, which
+    /// `--human` shows with units. Variances are in bytes squared and
+    /// histograms hold counts, so neither is.
+    pub fn in_bytes(&self) -> bool {
+        !matches!(self, AggregateFunction::Variance(_) | AggregateFunction::Histogram(..))
+            && self.field().is_some_and(|field| field.is_size())
+    }
+}
+
+impl Display for AggregateFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AggregateFunction::Count => write!(f, "Count"),
+            AggregateFunction::Sum(aggregator) => write!(f, "Sum of {}", aggregator),
+            AggregateFunction::Avg(aggregator) => write!(f, "Avg of {}", aggregator),
+            AggregateFunction::Max(aggregator) => write!(f, "Max of {}", aggregator),
+            AggregateFunction::Min(aggregator) => write!(f, "Min of {}", aggregator),
+            AggregateFunction::Median(aggregator) => write!(f, "Median of {}", aggregator),
+            AggregateFunction::Percentile(rank, aggregator) => write!(f, "P{} of {}", rank, aggregator),
+            AggregateFunction::StdDev(aggregator) => write!(f, "Std Dev of {}", aggregator),
+            AggregateFunction::Variance(aggregator) => write!(f, "Variance of {}", aggregator),
+            AggregateFunction::Mode(aggregator) => write!(f, "Mode of {}", aggregator),
+            AggregateFunction::Histogram(aggregator, _) => write!(f, "Histogram of {}", aggregator),
+        }
+    }
+}
+
+/// A HAVING expression: a boolean combination of conditions on aggregate values.
+pub type Having = BoolExpr<GroupPredicate>;
+
+/// A condition comparing the value of an aggregate over a group with a constant.
+///
+/// The aggregate is written like in `--aggregate` (`count`, `avg`), as a
+/// function call (`sum(size)`, `count(*)`, `max(modified)`) or by its output
+/// key (`sum_size`). The constant is parsed according to the type of the
+/// aggregate: a whole number for `count`, a decimal number for `variance`,
+/// a size with an optional unit such as `10M` or `1.5GiB` (see `parse_size`)
+/// for other aggregates over sizes, a duration such as `7d` for aggregates
+/// over `age` and the standard deviation of times, a date/time (see
+/// `parse_time`) for other aggregates over times, and otherwise a whole
+/// number, or a decimal number for `avg`, `median`, percentiles and `stddev`.
+/// Periods such as `yesterday` compare like in WHERE (see `TimeRange::compare`).
+#[derive(Debug, Clone)]
+pub struct GroupPredicate {
+    pub aggregate: AggregateFunction,
+    pub comparison: Comparison,
+    pub threshold: Threshold,
+}
+
+/// The constant a group predicate compares an aggregate with.
+///
+/// # Variants
+/// - `Value(Value)`: A number, compared with numeric aggregates
+/// - `Time(TimeRange)`: An instant or period, compared with aggregates yielding times
+#[derive(Debug, Clone, PartialEq)]
+pub enum Threshold {
+    Value(Value),
+    Time(TimeRange),
+}
+
+impl GroupPredicate {
+    /// Builds a group predicate from its already separated aggregate, operator and value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the aggregate or operator is unknown, or the value
+    /// does not match the type of the aggregate.
+    pub fn from_parts(aggregate: &str, operator: &str, value: &str) -> Result<GroupPredicate, String> {
+        let target = aggregate.trim();
+        let target = match target.strip_suffix(')').and_then(|call| call.split_once('(')) {
+            Some((function, argument)) => format!("{},{}", function, argument),
+            None => target.to_string(),
+        };
+        let aggregate = AggregateFunction::from_str(&target)
+            .or_else(|e| match target.split_once('_') {
+                Some((function, argument)) => AggregateFunction::from_str(&format!("{},{}", function, argument)),
+                None => Err(e),
+            })
+            .map_err(|_| format!("Invalid aggregate in HAVING condition: {}", aggregate.trim()))?;
+        if let AggregateFunction::Histogram(..) = aggregate {
+            return Err(format!("A histogram cannot be compared in a HAVING condition: {}", aggregate.key()));
+        }
+        let comparison = operator.trim().parse::<Comparison>()?;
+        let threshold = aggregate.parse_threshold(value.trim())?;
+        Ok(GroupPredicate { aggregate, comparison, threshold })
+    }
+
+    /// Checks whether the aggregate over a group satisfies the condition.
+    ///
+    /// Groups without a time to compare, such as empty groups, never satisfy
+    /// a condition on a time.
+    pub fn matches(&self, files: &[&File]) -> bool {
+        match (&self.threshold, self.aggregate.evaluate(files)) {
+            (Threshold::Time(range), Value::Time(time)) => range.compare(time, &self.comparison),
+            (Threshold::Time(_), _) => false,
+            (Threshold::Value(threshold), value) => self.comparison.compare(value, threshold.clone()),
+        }
+    }
+}
+
+impl Leaf for GroupPredicate {
+    type Subject<'a> = [&'a File];
+
+    const CLAUSE: &'static str = "HAVING";
+
+    fn from_parts(aggregate: &str, operator: &str, value: &str) -> Result<Self, String> {
+        GroupPredicate::from_parts(aggregate, operator, value)
+    }
+
+    fn matches(&self, files: &[&File]) -> bool {
+        GroupPredicate::matches(self, files)
+    }
+}
+
+impl AggregateFunction {
+    /// Parses the constant a HAVING condition compares this aggregate with.
+    ///
+    /// Sizes may be written with units (see `parse_size`), ages and spreads
+    /// of times as durations (see `parse_duration`) and times as date/time
+    /// literals.
+    fn parse_threshold(&self, s: &str) -> Result<Threshold, String> {
+        if let Some(field) = self.field()
+            && matches!(field, Field::Modified | Field::Accessed | Field::Created)
+            && !matches!(self, AggregateFunction::StdDev(_) | AggregateFunction::Variance(_))
+        {
+            return parse_time(s).map(Threshold::Time);
+        }
+        self.parse_value(s).map(Threshold::Value)
+    }
+
+    /// Parses a number of the type this aggregate yields; see `parse_threshold`.
+    fn parse_value(&self, s: &str) -> Result<Value, String> {
+        let invalid = || format!("Invalid number: {}", s);
+        let decimal = matches!(
+            self,
+            AggregateFunction::Avg(_)
+                | AggregateFunction::Median(_)
+                | AggregateFunction::Percentile(..)
+                | AggregateFunction::StdDev(_)
+        );
+        let Some(field) = self.field() else {
+            return s.parse::<u64>().map(Value::Integer).map_err(|_| invalid());
+        };
+        if matches!(self, AggregateFunction::Variance(_)) {
+            return s.parse::<f64>().map(Value::Float).map_err(|_| invalid());
+        }
+        let number = match field {
+            Field::Age | Field::Modified | Field::Accessed | Field::Created => parse_seconds(s)?,
+            field if field.is_size() => parse_size(s)?,
+            _ if decimal => return s.parse::<f64>().map(Value::Float).map_err(|_| invalid()),
+            _ => s.parse::<u64>().map_err(|_| invalid())?,
+        };
+        Ok(if decimal { Value::Float(number as f64) } else { Value::Integer(number) })
+    }
+}
+
+/// Parses a number of seconds, written either plainly or as a duration (see `parse_duration`).
+fn parse_seconds(s: &str) -> Result<u64, String> {
+    s.parse::<u64>()
+        .or_else(|_| parse_duration(s).map(|duration| duration.num_seconds().max(0) as u64))
+}
+
+/// Parses a plain count, such as a number of lines.
+fn parse_count(s: &str) -> Result<u64, String> {
+    s.parse::<u64>().map_err(|_| format!("Invalid number: {}", s))
+}
+
+/// Defines how a histogram divides the values of a field into buckets.
+///
+/// # Variants
+/// - `Numeric(SizeGrouping)`: Logarithmic, linear or custom buckets of a number,
+///   or one bucket per value (see `SizeGrouping`)
+/// - `Calendar(TimeGrouping)`: Calendar buckets of a time, e.g. `year,month`
+#[derive(Debug, Clone)]
+pub enum HistogramBuckets {
+    Numeric(SizeGrouping),
+    Calendar(TimeGrouping),
+}
+
+impl HistogramBuckets {
+    /// Parses the arguments following the field of a histogram, e.g. `log2`,
+    /// `linear,100` or `buckets,1d,1w,30d` for numbers and `year,month` for times.
+    ///
+    /// Bounds are read like HAVING constants: sizes with units, ages as
+    /// durations, and other numbers plainly. Without arguments, sizes default
+    /// to `log2`, depths and link counts to one bucket per value, and other
+    /// numbers to `log2`; times need calendar components.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for missing calendar components, for size units with
+    /// fields other than sizes, and for invalid groupings or bounds.
+    fn parse(aggregator: &ArithmeticAggregator, arguments: &[String]) -> Result<HistogramBuckets, String> {
+        if aggregator.is_temporal() {
+            return TimeGrouping::parse(arguments).map(HistogramBuckets::Calendar);
+        }
+        let field = aggregator.field();
+        if arguments.is_empty() {
+            let grouping = match field {
+                Field::Depth | Field::Links => SizeGrouping::Unit(SizeMagnitude::Bytes),
+                _ => SizeGrouping::Log2,
+            };
+            return Ok(HistogramBuckets::Numeric(grouping));
+        }
+        let parse_bound = match field {
+            field if field.is_size() => parse_size,
+            Field::Age => parse_seconds,
+            _ => parse_count,
+        };
+        let grouping = SizeGrouping::parse_with(arguments, parse_bound)?;
+        if let SizeGrouping::Unit(_) = grouping
+            && !field.is_size()
+        {
+            return Err(format!(
+                "Cannot divide {} into size units: use log2, log10, linear or buckets",
+                field.key()
+            ));
+        }
+        Ok(HistogramBuckets::Numeric(grouping))
+    }
+}
+
 /// Defines comparison criteria for file aggregation operations.
 ///
 /// This enum specifies which file attribute should be used when performing
@@ -57,15 +479,29 @@ impl FromStr for AggregateFunction {
 ///
 /// # Variants
 /// - `Size`: Compare files by their size in bytes
+/// - `DiskUsage`: Compare files by the disk space allocated to them
+/// - `Age`: Compare files by the time since their last modification
 /// - `Modified`: Compare files by their last modification time
 /// - `Accessed`: Compare files by their last access time
 /// - `Created`: Compare files by their creation time
+/// - `Lines`: Compare text files by their number of lines
+/// - `Words`: Compare text files by their number of words
+/// - `Chars`: Compare text files by their number of characters
+/// - `Depth`: Compare files by their depth below the analyzed directory
+/// - `Links`: Compare files by their number of hard links
 #[derive(Debug, Clone)]
 pub enum ComparingAggregator {
     Size,
+    DiskUsage,
+    Age,
     Modified,
     Accessed,
     Created,
+    Lines,
+    Words,
+    Chars,
+    Depth,
+    Links,
 }
 
 impl ComparingAggregator {
@@ -90,6 +526,30 @@ impl ComparingAggregator {
             ComparingAggregator::Modified => a.modified.cmp(&b.modified),
             ComparingAggregator::Accessed => a.accessed.cmp(&b.accessed),
             ComparingAggregator::Created => a.created.cmp(&b.created),
+            _ => self.field().value(a).compare(&self.field().value(b)),
+        }
+    }
+
+    /// Whether a file has the attribute this aggregator compares; binary
+    /// files and directories, for example, have no line count.
+    pub fn applies(&self, file: &File) -> bool {
+        self.field().value(file) != Value::Null
+    }
+
+    /// The file field this aggregator compares.
+    pub fn field(&self) -> Field {
+        match self {
+            ComparingAggregator::Size => Field::Size,
+            ComparingAggregator::DiskUsage => Field::DiskUsage,
+            ComparingAggregator::Age => Field::Age,
+            ComparingAggregator::Modified => Field::Modified,
+            ComparingAggregator::Accessed => Field::Accessed,
+            ComparingAggregator::Created => Field::Created,
+            ComparingAggregator::Lines => Field::Lines,
+            ComparingAggregator::Words => Field::Words,
+            ComparingAggregator::Chars => Field::Chars,
+            ComparingAggregator::Depth => Field::Depth,
+            ComparingAggregator::Links => Field::Links,
         }
     }
 }
@@ -100,9 +560,16 @@ impl FromStr for ComparingAggregator {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "size" | "s" => Ok(ComparingAggregator::Size),
+            "disk_usage" | "du" => Ok(ComparingAggregator::DiskUsage),
+            "age" => Ok(ComparingAggregator::Age),
             "modified" | "mod" | "m" => Ok(ComparingAggregator::Modified),
             "accessed" | "acc" | "a" => Ok(ComparingAggregator::Accessed),
             "created" | "cre" | "c" => Ok(ComparingAggregator::Created),
+            "lines" | "loc" => Ok(ComparingAggregator::Lines),
+            "words" => Ok(ComparingAggregator::Words),
+            "chars" | "characters" => Ok(ComparingAggregator::Chars),
+            "depth" | "d" => Ok(ComparingAggregator::Depth),
+            "nlink" | "links" | "hard_links" => Ok(ComparingAggregator::Links),
             _ => Err(format!("Unknown comparing aggregator: {}", s)),
         }
     }
@@ -110,173 +577,258 @@ impl FromStr for ComparingAggregator {
 
 impl Display for ComparingAggregator {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let name = match self {
-            ComparingAggregator::Size => "Size",
-            ComparingAggregator::Modified => "Modified",
-            ComparingAggregator::Accessed => "Accessed",
-            ComparingAggregator::Created => "Created",
-        };
-        write!(f, "{}", name)
+        write!(f, "{}", self.field())
     }
 }
 
 
-/// Finds the file with the maximum value for the specified comparison criterion.
-///
-/// This function searches through a collection of grouped files and returns, for each group,
-/// the file that has the highest value according to the specified `ComparingAggregator`.
-/// For example, it can find the largest file, the most recently modified file,
-/// or the most recently accessed file in each group.
-///
-/// # Arguments
-///
-/// * `files` - A map from group key to a vector of file references
-/// * `aggregator` - The comparison criterion to use for finding the maximum
-///
-/// # Returns
-///
-/// A `HashMap<String, &File>` mapping each group key to the file with the maximum value.
-pub fn max<'a>(files: &'a HashMap<String, Vec<&'a File>>, aggregator: ComparingAggregator) -> HashMap<String, &'a File> {
-    files
-        .iter()
-        .map(|(key, file_list)| {
-            let max_file = file_list
-                .iter()
-                .cloned()
-                .max_by(|a, b| aggregator.compare(a, b))
-                .unwrap();
-            (key.clone(), max_file)
-        })
-        .collect()
-}
-
-/// Finds the file with the minimum value for the specified comparison criterion.
-///
-/// This function searches through a collection of grouped files and returns, for each group,
-/// the file that has the lowest value according to the specified `ComparingAggregator`.
-/// For example, it can find the smallest file, the oldest modified file,
-/// or the least recently accessed file in each group.
-///
-/// # Arguments
-///
-/// * `files` - A map from group key to a vector of file references
-/// * `aggregator` - The comparison criterion to use for finding the minimum
-///
-/// # Returns
-///
-/// A `HashMap<String, &File>` mapping each group key to the file with the minimum value.
-pub fn min<'a>(files: &'a HashMap<String, Vec<&'a File>>, aggregator: ComparingAggregator) -> HashMap<String, &'a File> {
-    files
-        .iter()
-        .map(|(key, file_list)| {
-            let min_file = file_list
-                .iter()
-                .cloned()
-                .min_by(|a, b| aggregator.compare(a, b))
-                .unwrap();
-            (key.clone(), min_file)
-        })
-        .collect()
-}
-
 /// Defines arithmetic aggregation criteria for file operations.
 ///
 /// This enum specifies which numeric file attribute should be used when
 /// performing arithmetic operations such as sum or average calculations.
-/// Currently focused on size-based calculations but can be extended for
-/// other numeric properties.
 ///
 /// # Variants
 /// - `Size`: Perform arithmetic operations on file sizes in bytes
+/// - `DiskUsage`: Perform arithmetic operations on the disk space allocated
+///   to files, in bytes, which differs from the size for sparse and small files
+/// - `Age`: Perform arithmetic operations on the time since the last modification, in seconds
+/// - `Modified`: Perform arithmetic operations on modification times, as seconds since 1970
+/// - `Accessed`: Perform arithmetic operations on access times, as seconds since 1970
+/// - `Created`: Perform arithmetic operations on creation times, as seconds since 1970
+/// - `Lines`: Perform arithmetic operations on the line counts of text files
+/// - `Words`: Perform arithmetic operations on the word counts of text files
+/// - `Chars`: Perform arithmetic operations on the character counts of text files
+/// - `Depth`: Perform arithmetic operations on the depths below the analyzed directory
+/// - `Links`: Perform arithmetic operations on the numbers of hard links
+///
+/// Sums of times are meaningless, so `AggregateFunction::from_str` rejects
+/// them; see `is_temporal`. Only `DiskUsage` counts hard links to the same
+/// file once; see `counted`.
 #[derive(Debug, Clone)]
 pub enum ArithmeticAggregator {
     Size,
+    DiskUsage,
+    Age,
+    Modified,
+    Accessed,
+    Created,
+    Lines,
+    Words,
+    Chars,
+    Depth,
+    Links,
+}
+
+impl ArithmeticAggregator {
+    /// Extracts the numeric value this aggregator operates on from a file.
+    ///
+    /// Times are extracted as seconds since 1970. Returns `None` for files
+    /// without the value, e.g. binary files for line counts, so they do not
+    /// drag down averages.
+    pub fn extract(&self, file: &File) -> Option<i64> {
+        match self {
+            ArithmeticAggregator::Size => Some(file.size as i64),
+            ArithmeticAggregator::DiskUsage => Some(file.disk_usage as i64),
+            ArithmeticAggregator::Modified => Some(file.modified.timestamp()),
+            ArithmeticAggregator::Accessed => Some(file.accessed.timestamp()),
+            ArithmeticAggregator::Created => Some(file.created.timestamp()),
+            _ => match self.field().value(file) {
+                Value::Integer(number) => Some(number as i64),
+                _ => None,
+            },
+        }
+    }
+
+    /// The files of a group this aggregator operates on.
+    ///
+    /// For `DiskUsage`, hard links to the same file are counted once (see
+    /// `unique_files`), so totals match what `du` reports; every other
+    /// aggregator counts each listed entry, so sums equal averages times counts.
+    pub fn counted<'a>(&self, files: &[&'a File]) -> Vec<&'a File> {
+        match self {
+            ArithmeticAggregator::DiskUsage => unique_files(files),
+            _ => files.to_vec(),
+        }
+    }
+
+    /// Whether this aggregator operates on points in time rather than amounts.
+    pub fn is_temporal(&self) -> bool {
+        matches!(self, ArithmeticAggregator::Modified | ArithmeticAggregator::Accessed | ArithmeticAggregator::Created)
+    }
+
+    /// Converts a number computed from the extracted values, such as their
+    /// mean or a percentile, to a `Value`: a time for temporal aggregators
+    /// and a decimal number otherwise.
+    pub fn to_value(&self, number: f64) -> Value {
+        if !self.is_temporal() {
+            return Value::Float(number);
+        }
+        DateTime::from_timestamp(number.round() as i64, 0)
+            .map_or(Value::Null, |time| Value::Time(time.with_timezone(&Local)))
+    }
+
+    /// The file field this aggregator operates on.
+    pub fn field(&self) -> Field {
+        match self {
+            ArithmeticAggregator::Size => Field::Size,
+            ArithmeticAggregator::DiskUsage => Field::DiskUsage,
+            ArithmeticAggregator::Age => Field::Age,
+            ArithmeticAggregator::Modified => Field::Modified,
+            ArithmeticAggregator::Accessed => Field::Accessed,
+            ArithmeticAggregator::Created => Field::Created,
+            ArithmeticAggregator::Lines => Field::Lines,
+            ArithmeticAggregator::Words => Field::Words,
+            ArithmeticAggregator::Chars => Field::Chars,
+            ArithmeticAggregator::Depth => Field::Depth,
+            ArithmeticAggregator::Links => Field::Links,
+        }
+    }
+}
+
+impl FromStr for ArithmeticAggregator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "size" | "s" => Ok(ArithmeticAggregator::Size),
+            "disk_usage" | "du" => Ok(ArithmeticAggregator::DiskUsage),
+            "age" => Ok(ArithmeticAggregator::Age),
+            "modified" | "mod" | "m" | "mtime" => Ok(ArithmeticAggregator::Modified),
+            "accessed" | "acc" | "a" | "atime" => Ok(ArithmeticAggregator::Accessed),
+            "created" | "cre" | "c" => Ok(ArithmeticAggregator::Created),
+            "lines" | "loc" => Ok(ArithmeticAggregator::Lines),
+            "words" => Ok(ArithmeticAggregator::Words),
+            "chars" | "characters" => Ok(ArithmeticAggregator::Chars),
+            "depth" | "d" => Ok(ArithmeticAggregator::Depth),
+            "nlink" | "links" | "hard_links" => Ok(ArithmeticAggregator::Links),
+            _ => Err(format!("Unknown arithmetic aggregator: {}", s)),
+        }
+    }
 }
 
 impl Display for ArithmeticAggregator {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let name = match self {
-            ArithmeticAggregator::Size => "Size",
-        };
-        write!(f, "{}", name)
+        write!(f, "{}", self.field())
     }
 }
 
-/// Calculates the sum of a numeric property across all files.
-///
-/// This function aggregates a numeric value from all files in each group according to the specified
-/// `ArithmeticAggregator`. Currently supports summing file sizes, but can be extended for other numeric properties.
-///
-/// # Arguments
-///
-/// * `files` - A map from group key to a vector of file references
-/// * `aggregator` - The arithmetic criterion specifying which property to sum
+/// Drops repeated hard links to the same file from a group.
 ///
-/// # Returns
-///
-/// A `HashMap<String, u64>` mapping each group key to the sum for that group.
-pub fn sum(files: &HashMap<String, Vec<&File>>, aggregator: ArithmeticAggregator) -> HashMap<String, u64> {
+/// Files are identified by their device and inode numbers. Only files with
+/// more than one link are tracked, and files without an inode number (on
+/// platforms that do not report one) are always kept, so the result matches
+/// what `du` counts.
+pub fn unique_files<'a>(files: &[&'a File]) -> Vec<&'a File> {
+    let mut seen = HashSet::new();
     files
         .iter()
-        .map(|(key, file_list)| {
-            let total: u64 = file_list.iter().map(|file| match aggregator {
-                ArithmeticAggregator::Size => file.size,
-            }).sum();
-            (key.clone(), total)
-        })
+        .copied()
+        .filter(|file| file.nlink <= 1 || file.inode == 0 || seen.insert((file.device, file.inode)))
         .collect()
 }
 
-/// Calculates the average of a numeric property across all files in each group.
+/// One bucket of a histogram: its label, a number ordering it among the
+/// other buckets, and its number of files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bucket {
+    pub label: String,
+    pub rank: u64,
+    pub count: u64,
+}
+
+/// Counts the files of a group per bucket of a field, such as `size,log2`,
+/// `lines,linear,100` or `modified,year,month`.
 ///
-/// This function computes the arithmetic mean of a numeric value from all files in each group
-/// according to the specified `ArithmeticAggregator`. The calculation uses the `sum` function
-/// internally and divides by the count of files in each group.
+/// Files without the value, e.g. binary files for line counts, are left
+/// out, and hard links are counted once for `DiskUsage` (see `counted`).
+/// Numeric bounds of sizes are labelled like size literals (see
+/// `format_bound`) and others as plain numbers. Buckets without files are
+/// left out.
 ///
 /// # Arguments
 ///
-/// * `files` - A map from group key to a vector of file references
-/// * `aggregator` - The arithmetic criterion specifying which property to average
+/// * `files` - The files of the group
+/// * `aggregator` - The field whose values are counted
+/// * `buckets` - How the values are divided into buckets
 ///
 /// # Returns
 ///
-/// A `HashMap<String, f64>` mapping each group key to the average value for that group.
-pub fn avg(files: &HashMap<String, Vec<&File>>, aggregator: ArithmeticAggregator) -> HashMap<String, f64> {
-    let sum = sum(files, aggregator.clone());
+/// The non-empty buckets, in ascending order of their values.
+pub fn histogram(files: &[&File], aggregator: &ArithmeticAggregator, buckets: &HistogramBuckets) -> Vec<Bucket> {
+    let format: fn(u64) -> String = if aggregator.field().is_size() { format_bound } else { |value| value.to_string() };
+    let mut counts: HashMap<String, Bucket> = HashMap::new();
+    for file in aggregator.counted(files) {
+        let (label, rank) = match (buckets, aggregator.field().value(file)) {
+            (HistogramBuckets::Calendar(grouping), Value::Time(time)) => (grouping.format(time), grouping.rank(time)),
+            (HistogramBuckets::Numeric(grouping), _) => match aggregator.extract(file) {
+                Some(value) => {
+                    let value = value.max(0) as u64;
+                    (grouping.label(value, format), grouping.rank(value))
+                }
+                None => continue,
+            },
+            _ => continue,
+        };
+        counts.entry(label.clone()).or_insert_with(|| Bucket { label, rank, count: 0 }).count += 1;
+    }
+    let mut buckets: Vec<Bucket> = counts.into_values().collect();
+    buckets.sort_by(|a, b| a.rank.cmp(&b.rank).then_with(|| a.label.cmp(&b.label)));
+    buckets
+}
 
-    files
-        .iter()
-        .map(|(key, file_list)| {
-            let count = file_list.len() as f64;
-            if count == 0.0 {
-                (key.clone(), 0.0) // Avoid division by zero
-            } else {
-                let total = sum.get(key).unwrap_or(&0);
-                (key.clone(), *total as f64 / count)
-            }
-        })
-        .collect()
+/// Extracts the values of an arithmetic aggregator from a group, counting
+/// hard links to the same file once for `DiskUsage` (see `counted`), sorted
+/// in ascending order.
+fn sorted_values(files: &[&File], aggregator: &ArithmeticAggregator) -> Vec<i64> {
+    let mut values: Vec<i64> = aggregator.counted(files).iter().filter_map(|file| aggregator.extract(file)).collect();
+    values.sort_unstable();
+    values
 }
 
-/// Counts the number of files in each group.
+/// Computes a percentile of sorted values, interpolating linearly between
+/// the two closest ranks like spreadsheet `PERCENTILE.INC`.
 ///
 /// # Arguments
 ///
-/// * `files` - A map from group key to a vector of file references
+/// * `sorted` - The values, in ascending order
+/// * `rank` - The percentile to compute, between 0 and 100
 ///
 /// # Returns
 ///
-/// A `HashMap<String, u64>` mapping each group key to the count of files in that group.
-pub fn count(files: &HashMap<String, Vec<&File>>) -> HashMap<String, u64> {
-    files
-        .iter()
-        .map(|(key, file_list)| (key.clone(), file_list.len() as u64))
-        .collect()
+/// The percentile, or `None` if there are no values.
+pub fn percentile(sorted: &[i64], rank: f64) -> Option<f64> {
+    let last = sorted.len().checked_sub(1)?;
+    let position = rank / 100.0 * last as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    let fraction = position - lower as f64;
+    Some(sorted[lower] as f64 + (sorted[upper] - sorted[lower]) as f64 * fraction)
+}
+
+/// Computes the population variance of values, i.e. the mean squared
+/// deviation from their mean, or `None` if there are no values.
+pub fn variance(values: &[i64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let count = values.len() as f64;
+    let mean = values.iter().map(|value| *value as f64).sum::<f64>() / count;
+    Some(values.iter().map(|value| (*value as f64 - mean).powi(2)).sum::<f64>() / count)
+}
+
+/// Finds the most frequent of sorted values, preferring the smallest on
+/// ties, or `None` if there are no values.
+pub fn mode(sorted: &[i64]) -> Option<i64> {
+    sorted
+        .chunk_by(|a, b| a == b)
+        .max_by(|a, b| a.len().cmp(&b.len()).then(b[0].cmp(&a[0])))
+        .map(|run| run[0])
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::file::FileType;
     use chrono::{DateTime, Local, TimeZone};
     use std::collections::HashMap;
 
@@ -292,30 +844,39 @@ mod tests {
         vec![
             File {
                 name: "file1.txt".to_string(),
+                path: "file1.txt".to_string(),
+                depth: 1,
                 extension: "txt".to_string(),
                 size: 1000,
                 modified: now,
                 accessed: now,
                 created: now,
-                file_type: "file".to_string(),
+                file_type: FileType::File,
+                ..Default::default()
             },
             File {
                 name: "file2.rs".to_string(),
+                path: "file2.rs".to_string(),
+                depth: 1,
                 extension: "rs".to_string(),
                 size: 2048,
                 modified: earlier,
                 accessed: earlier,
                 created: earlier,
-                file_type: "file".to_string(),
+                file_type: FileType::File,
+                ..Default::default()
             },
             File {
                 name: "file3.txt".to_string(),
+                path: "file3.txt".to_string(),
+                depth: 1,
                 extension: "txt".to_string(),
                 size: 4096,
                 modified: oldest,
                 accessed: oldest,
                 created: oldest,
-                file_type: "file".to_string(),
+                file_type: FileType::File,
+                ..Default::default()
             },
         ]
     }
@@ -328,55 +889,295 @@ mod tests {
         map
     }
 
+    /// Evaluates an aggregate, written like in `--aggregate`, over every group.
+    fn evaluate_all(grouped: &HashMap<String, Vec<&File>>, function: &str) -> HashMap<String, Value> {
+        let function = AggregateFunction::from_str(function).unwrap();
+        grouped.iter().map(|(key, files)| (key.clone(), function.evaluate(files))).collect()
+    }
+
     #[test]
     fn test_max_size() {
         let files = sample_files();
         let grouped = group_by_ext(&files);
-        let max_map = max(&grouped, ComparingAggregator::Size);
-        assert_eq!(max_map["txt"].size, 4096);
-        assert_eq!(max_map["rs"].size, 2048);
+        let max = AggregateFunction::Max(ComparingAggregator::Size);
+        assert_eq!(max.select(&grouped["txt"]).unwrap().size, 4096);
+        assert_eq!(max.select(&grouped["rs"]).unwrap().size, 2048);
     }
 
     #[test]
     fn test_min_size() {
         let files = sample_files();
         let grouped = group_by_ext(&files);
-        let min_map = min(&grouped, ComparingAggregator::Size);
-        assert_eq!(min_map["txt"].size, 1000);
-        assert_eq!(min_map["rs"].size, 2048);
+        let min = AggregateFunction::Min(ComparingAggregator::Size);
+        assert_eq!(min.select(&grouped["txt"]).unwrap().size, 1000);
+        assert_eq!(min.select(&grouped["rs"]).unwrap().size, 2048);
+        assert!(AggregateFunction::Count.select(&grouped["txt"]).is_none());
     }
 
     #[test]
     fn test_sum_size() {
         let files = sample_files();
         let grouped = group_by_ext(&files);
-        let sum_map = sum(&grouped, ArithmeticAggregator::Size);
-        assert_eq!(sum_map["txt"], 1000 + 4096);
-        assert_eq!(sum_map["rs"], 2048);
+        let sums = evaluate_all(&grouped, "sum,size");
+        assert_eq!(sums["txt"], Value::Integer(1000 + 4096));
+        assert_eq!(sums["rs"], Value::Integer(2048));
+
+        // Files modified in the future have no age rather than a negative one
+        let future = File { modified: Local::now() + chrono::TimeDelta::days(1), ..Default::default() };
+        assert_eq!(AggregateFunction::from_str("sum,age").unwrap().evaluate(&[&future]), Value::Integer(0));
     }
 
     #[test]
     fn test_average_size() {
         let files = sample_files();
         let grouped = group_by_ext(&files);
-        let avg_map = avg(&grouped, ArithmeticAggregator::Size);
-        assert!((avg_map["txt"] - ((1000.0 + 4096.0) / 2.0)).abs() < 1e-6);
-        assert!((avg_map["rs"] - 2048.0).abs() < 1e-6);
+        let averages = evaluate_all(&grouped, "avg,size");
+        assert_eq!(averages["txt"], Value::Float((1000.0 + 4096.0) / 2.0));
+        assert_eq!(averages["rs"], Value::Float(2048.0));
     }
 
     #[test]
     fn test_count() {
         let files = sample_files();
         let grouped = group_by_ext(&files);
-        let count_map = count(&grouped);
-        assert_eq!(count_map["txt"], 2);
-        assert_eq!(count_map["rs"], 1);
+        let counts = evaluate_all(&grouped, "count");
+        assert_eq!(counts["txt"], Value::Integer(2));
+        assert_eq!(counts["rs"], Value::Integer(1));
+    }
+
+    #[test]
+    fn test_aggregate_values() {
+        let files = sample_files();
+        let grouped = group_by_ext(&files);
+        assert_eq!(evaluate_all(&grouped, "max,size")["txt"], Value::Integer(4096));
+        assert_eq!(evaluate_all(&grouped, "max,modified")["txt"], Value::Time(dt(1_000_000)));
+        assert_eq!(evaluate_all(&grouped, "min,modified")["txt"], Value::Time(dt(1_000_000 - 7200)));
+    }
+
+    #[test]
+    fn test_disk_usage_counts_hard_links_once() {
+        let mut files = sample_files();
+        for (index, file) in files.iter_mut().enumerate() {
+            file.device = 1;
+            file.inode = 10 + index as u64;
+            file.nlink = 1;
+            file.disk_usage = 4096;
+        }
+        // The first and third file are links to the same inode
+        files[0].nlink = 2;
+        files[2].inode = files[0].inode;
+        files[2].nlink = 2;
+        files[2].size = files[0].size;
+        let refs: Vec<&File> = files.iter().collect();
+
+        assert_eq!(unique_files(&refs).len(), 2);
+        let disk_usage = AggregateFunction::from_str("sum,disk_usage").unwrap();
+        assert_eq!(disk_usage.evaluate(&refs), Value::Integer(8192));
+        assert_eq!(disk_usage.key(), "sum_disk_usage");
+        // Sizes count every listed entry, so sums stay averages times counts
+        let size = AggregateFunction::from_str("sum,size").unwrap();
+        assert_eq!(size.evaluate(&refs), Value::Integer(2 * files[0].size + files[1].size));
+        let average = AggregateFunction::from_str("avg,size").unwrap();
+        assert_eq!(average.evaluate(&refs), Value::Float((2 * files[0].size + files[1].size) as f64 / 3.0));
+        assert!(AggregateFunction::from_str("sum,colour").is_err());
+    }
+
+    #[test]
+    fn test_having_conditions() {
+        let files = sample_files();
+        let grouped = group_by_ext(&files);
+
+        let having = Having::from_str("count > 1").unwrap();
+        assert!(having.matches(&grouped["txt"]));
+        assert!(!having.matches(&grouped["rs"]));
+
+        let having = Having::from_str("sum(size) >= 2048 AND NOT avg < 3000").unwrap();
+        assert!(!having.matches(&grouped["rs"]));
+        let having = Having::from_str("max_size,eq,4096 OR count(*) = 5").unwrap();
+        assert!(having.matches(&grouped["txt"]));
+
+        assert!(Having::from_str("ext = rs").is_err());
+        assert!(Having::from_str("count > many").is_err());
+        assert!(Having::from_str("avg ~ 2").is_err());
+    }
+
+    #[test]
+    fn test_having_size_units() {
+        let files = sample_files();
+        let grouped = group_by_ext(&files);
+
+        let having = Having::from_str("max(size) = 4k AND sum(size) > 1kb").unwrap();
+        assert!(having.matches(&grouped["txt"]));
+        let having = Having::from_str("avg(size) >= 1.5KiB").unwrap();
+        assert!(matches!(&having, Having::Predicate(predicate) if predicate.threshold == Threshold::Value(Value::Float(1536.0))));
+        assert!(Having::from_str("count > 1k").is_err());
+        assert!(Having::from_str("sum(size) > 1 parsec").is_err());
+    }
+
+    #[test]
+    fn test_having_time_periods() {
+        let files = sample_files();
+        let refs: Vec<&File> = files.iter().collect();
+        let newest = crate::utilities::time::zone().convert(dt(1_000_000));
+        let day = newest.format("%Y-%m-%d").to_string();
+        let matches = |condition: String| Having::from_str(&condition).unwrap().matches(&refs);
+
+        assert!(matches(format!("max(modified) = {}", day)));
+        assert!(matches(format!("max(modified) >= {}", day)));
+        assert!(!matches(format!("max(modified) > {}", day)));
+        assert!(matches(format!("max(modified) < {}", newest.date_naive().succ_opt().unwrap())));
+        assert!(matches(format!("min(modified) <= {}", day)));
+        assert!(!matches("max(modified) > 1970-01-01T00:00 AND count = 0".to_string()));
+    }
+
+    #[test]
+    fn test_text_aggregates() {
+        let root = std::env::temp_dir().join(format!("lsdir-aggregate-text-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("a.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        std::fs::write(root.join("b.rs"), "one\ntwo\nthree\nfour").unwrap();
+        // Binary files have no line count and do not affect the results
+        std::fs::write(root.join("c.bin"), b"\x00\x01\n\n\n\n\n\n").unwrap();
+
+        let files = crate::walk::walk(&root, &crate::walk::WalkOptions::default()).unwrap();
+        let refs: Vec<&File> = files.iter().collect();
+        let evaluate = |function: &str| AggregateFunction::from_str(function).unwrap().evaluate(&refs);
+        assert_eq!(evaluate("sum,lines"), Value::Integer(6));
+        assert_eq!(evaluate("avg,lines"), Value::Float(3.0));
+        assert_eq!(evaluate("max,lines"), Value::Integer(4));
+        assert_eq!(evaluate("min,lines"), Value::Integer(2));
+        assert_eq!(evaluate("sum,words"), Value::Integer(10));
+        assert_eq!(AggregateFunction::from_str("sum,loc").unwrap().key(), "sum_lines");
+
+        let having = Having::from_str("sum(lines) > 5 AND max(words) = 6").unwrap();
+        assert!(having.matches(&refs));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_temporal_aggregates() {
+        let files = sample_files();
+        let grouped = group_by_ext(&files);
+
+        let mean = evaluate_all(&grouped, "avg,modified");
+        assert_eq!(mean["txt"], Value::Time(dt(1_000_000 - 3600)));
+        assert_eq!(mean["rs"], Value::Time(dt(1_000_000 - 3600)));
+
+        let now = crate::utilities::time::now().timestamp();
+        let ages = evaluate_all(&grouped, "avg,age");
+        assert_eq!(ages["txt"], Value::Float((now - 1_000_000 + 3600) as f64));
+        let oldest = evaluate_all(&grouped, "max,age");
+        assert_eq!(oldest["txt"], Value::Integer((now - 1_000_000 + 7200) as u64));
+        let total = evaluate_all(&grouped, "sum,depth");
+        assert_eq!(total["txt"], Value::Integer(2));
+
+        let refs: Vec<&File> = files.iter().collect();
+        assert!(Having::from_str("avg(modified) < 2000-01-01").unwrap().matches(&refs));
+        assert!(Having::from_str("avg(age) > 1d AND max(age) > 3600").unwrap().matches(&refs));
+        assert_eq!(AggregateFunction::from_str("avg,mtime").unwrap().key(), "avg_modified");
+    }
+
+    #[test]
+    fn test_invalid_aggregate_fields() {
+        let error = |function: &str| AggregateFunction::from_str(function).unwrap_err();
+        assert!(error("sum,modified").contains("Cannot compute sum of modified"));
+        assert!(error("avg,name").contains("Cannot compute avg of name"));
+        assert!(error("max,extension").contains("Cannot compute max of extension"));
+        assert_eq!(error("min,bogus"), "Unknown field in min: bogus");
+        assert!(error("max").contains("Missing argument"));
+        assert!(error("count,size").contains("count takes no field"));
+        assert!(matches!(AggregateFunction::from_str("count,*"), Ok(AggregateFunction::Count)));
+    }
+
+    #[test]
+    fn test_statistics() {
+        assert_eq!(percentile(&[10, 20, 30, 40], 50.0), Some(25.0));
+        assert_eq!(percentile(&[10, 20, 30, 40], 0.0), Some(10.0));
+        assert_eq!(percentile(&[10, 20, 30, 40], 100.0), Some(40.0));
+        assert_eq!(percentile(&[10, 20, 30, 40, 50], 90.0), Some(46.0));
+        assert_eq!(percentile(&[], 50.0), None);
+        assert_eq!(variance(&[2, 4, 4, 4, 5, 5, 7, 9]), Some(4.0));
+        assert_eq!(variance(&[]), None);
+        assert_eq!(mode(&[1, 2, 2, 3, 3]), Some(2));
+        assert_eq!(mode(&[5]), Some(5));
+        assert_eq!(mode(&[]), None);
+    }
+
+    #[test]
+    fn test_statistical_aggregates() {
+        let files = sample_files();
+        let grouped = group_by_ext(&files);
+        let values = |function: &str| evaluate_all(&grouped, function);
+
+        assert_eq!(values("median")["txt"], Value::Float(2548.0));
+        assert_eq!(values("p75,size")["txt"], Value::Float(3322.0));
+        assert_eq!(values("stddev,size")["txt"], Value::Float(1548.0));
+        assert_eq!(values("variance,size")["rs"], Value::Float(0.0));
+        assert_eq!(values("mode,size")["txt"], Value::Integer(1000));
+        assert_eq!(values("median,modified")["txt"], Value::Time(dt(1_000_000 - 3600)));
+        assert_eq!(values("mode,modified")["rs"], Value::Time(dt(1_000_000 - 3600)));
+        assert_eq!(values("stddev,modified")["txt"], Value::Float(3600.0));
+
+        assert_eq!(AggregateFunction::from_str("p99.9,du").unwrap().key(), "p99.9_disk_usage");
+        assert_eq!(AggregateFunction::from_str("median").unwrap().to_string(), "Median of Size");
+        assert!(AggregateFunction::from_str("p101,size").is_err());
+        assert!(AggregateFunction::from_str("median,name").unwrap_err().contains("Cannot compute median of name"));
+        assert!(AggregateFunction::from_str("stddev,size").unwrap().in_bytes());
+        assert!(!AggregateFunction::from_str("variance,size").unwrap().in_bytes());
+
+        let refs: Vec<&File> = files.iter().collect();
+        assert!(Having::from_str("p90(size) > 3K AND median(size) < 2.5K AND count = 3").unwrap().matches(&refs));
+        assert!(Having::from_str("stddev(modified) < 1h AND variance(size) > 1000").unwrap().matches(&refs));
+    }
+
+    #[test]
+    fn test_histogram() {
+        let files = sample_files();
+        let refs: Vec<&File> = files.iter().collect();
+        let AggregateFunction::Histogram(aggregator, buckets) = AggregateFunction::from_str("histogram,size,log2").unwrap()
+        else {
+            unreachable!()
+        };
+        let labels: Vec<(String, u64)> =
+            histogram(&refs, &aggregator, &buckets).into_iter().map(|bucket| (bucket.label, bucket.count)).collect();
+        assert_eq!(labels, vec![("512–1K".to_string(), 1), ("2K–4K".to_string(), 1), ("4K–8K".to_string(), 1)]);
+
+        let function = AggregateFunction::from_str("histogram,size,buckets,2K").unwrap();
+        assert_eq!(function.evaluate(&refs), Value::Text("<2K: 1, ≥2K: 2".to_string()));
+        assert_eq!(function.key(), "histogram_size");
+        assert!(!function.in_bytes());
+        assert!(matches!(
+            AggregateFunction::from_str("hist").unwrap(),
+            AggregateFunction::Histogram(ArithmeticAggregator::Size, HistogramBuckets::Numeric(SizeGrouping::Log2))
+        ));
+        assert!(AggregateFunction::from_str("histogram,ext").unwrap_err().contains("Cannot compute histogram of extension"));
+        assert!(AggregateFunction::from_str("histogram,modified").is_err());
+        assert!(AggregateFunction::from_str("histogram,lines,kb").unwrap_err().contains("Cannot divide lines into size units"));
+        assert!(Having::from_str("histogram > 1").is_err());
+    }
+
+    #[test]
+    fn test_histogram_of_other_fields() {
+        let mut files = sample_files();
+        for (file, depth) in files.iter_mut().zip([1, 2, 5]) {
+            file.depth = depth;
+        }
+        let refs: Vec<&File> = files.iter().collect();
+        let evaluate = |spec: &str| AggregateFunction::from_str(spec).unwrap().evaluate(&refs);
+        assert_eq!(evaluate("histogram,depth"), Value::Text("1: 1, 2: 1, 5: 1".to_string()));
+        assert_eq!(evaluate("histogram,depth,linear,2"), Value::Text("0–2: 1, 2–4: 1, 4–6: 1".to_string()));
+        assert_eq!(evaluate("histogram,depth,buckets,2,4"), Value::Text("<2: 1, 2–4: 1, ≥4: 1".to_string()));
+        assert_eq!(evaluate("histogram,modified,year"), Value::Text("1970: 3".to_string()));
+        assert!(AggregateFunction::from_str("histogram,age,buckets,1h,1d").is_ok());
+        assert!(AggregateFunction::from_str("histogram,lines,log2").is_ok());
     }
 
     #[test]
     fn test_average_empty_group() {
-        let grouped: HashMap<String, Vec<&File>> = HashMap::new();
-        let avg_map = avg(&grouped, ArithmeticAggregator::Size);
-        assert!(avg_map.is_empty());
+        let empty: Vec<&File> = Vec::new();
+        assert_eq!(AggregateFunction::from_str("avg,size").unwrap().evaluate(&empty), Value::Float(0.0));
+        assert_eq!(AggregateFunction::from_str("avg,modified").unwrap().evaluate(&empty), Value::Float(0.0));
+        assert_eq!(AggregateFunction::from_str("max,size").unwrap().evaluate(&empty), Value::Text(String::new()));
     }
 }
\ No newline at end of file
@@ -0,0 +1,137 @@
+use crate::content::{hash_file, HashAlgorithm, PARTIAL_HASH_BYTES};
+use crate::file::{File, FileType};
+use crate::utilities::aggregate::unique_files;
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A set of regular files with identical contents.
+///
+/// # Fields
+/// - `size`: The size of each file in bytes
+/// - `hash`: The content hash shared by all files
+/// - `files`: The identical files, ordered by path
+#[derive(Debug, Clone)]
+pub struct DuplicateSet<'a> {
+    pub size: u64,
+    pub hash: String,
+    pub files: Vec<&'a File>,
+}
+
+impl DuplicateSet<'_> {
+    /// The space freed by keeping only one of the files.
+    pub fn reclaimable(&self) -> u64 {
+        self.size * (self.files.len() as u64 - 1)
+    }
+}
+
+/// Finds sets of regular files with identical contents.
+///
+/// To avoid reading files that cannot have a duplicate, candidates are
+/// narrowed down in stages: first by size, then by the hash of their first
+/// `PARTIAL_HASH_BYTES` bytes, and only then by the hash of their full
+/// contents. Empty files and everything other than regular files are
+/// ignored, and hard links to the same file count as one file, since
+/// removing one of them frees no space. Files that cannot be read are
+/// reported on stderr and skipped.
+///
+/// # Arguments
+///
+/// * `files` - The files to search
+/// * `algorithm` - The hash function that decides whether contents are identical
+///
+/// # Returns
+///
+/// The duplicate sets, the ones freeing the most space first.
+pub fn find_duplicates<'a>(files: &[&'a File], algorithm: HashAlgorithm) -> Vec<DuplicateSet<'a>> {
+    let candidates: Vec<&File> = unique_files(files)
+        .into_iter()
+        .filter(|file| file.file_type == FileType::File && file.size > 0)
+        .collect();
+
+    let by_size = split(vec![candidates], |file| Some(file.size));
+    let by_partial_hash = split(by_size, |file| {
+        if file.size <= PARTIAL_HASH_BYTES {
+            // The full hash reads no more than the partial hash would
+            return Some(String::new());
+        }
+        hash_file(&file.full_path, HashAlgorithm::Xxh3, Some(PARTIAL_HASH_BYTES))
+            .map_err(|e| eprintln!("Warning: Could not hash file {:?}: {}", file.full_path, e))
+            .ok()
+    });
+    let by_hash = split(by_partial_hash, |file| file.hash(algorithm).map(str::to_string));
+
+    let mut sets: Vec<DuplicateSet> = by_hash
+        .into_iter()
+        .map(|mut files| {
+            files.sort_by(|a, b| a.path.cmp(&b.path));
+            DuplicateSet {
+                size: files[0].size,
+                hash: files[0].hash(algorithm).unwrap_or_default().to_string(),
+                files,
+            }
+        })
+        .collect();
+    sets.sort_by(|a, b| {
+        b.reclaimable()
+            .cmp(&a.reclaimable())
+            .then_with(|| a.files[0].path.cmp(&b.files[0].path))
+    });
+    sets
+}
+
+/// Splits every group by `key`, keeping only the parts with at least two
+/// files. Files without a key are dropped.
+fn split<K: Eq + Hash>(groups: Vec<Vec<&File>>, key: impl Fn(&File) -> Option<K>) -> Vec<Vec<&File>> {
+    groups
+        .into_iter()
+        .flat_map(|files| {
+            let mut parts: HashMap<K, Vec<&File>> = HashMap::new();
+            for file in files {
+                if let Some(key) = key(file) {
+                    parts.entry(key).or_default().push(file);
+                }
+            }
+            parts.into_values().filter(|part| part.len() > 1)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::walk::{walk, WalkOptions};
+    use std::fs;
+
+    #[test]
+    fn test_find_duplicates() {
+        let root = std::env::temp_dir().join(format!("lsdir-duplicates-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("copies")).unwrap();
+        let large = "x".repeat(10_000);
+        fs::write(root.join("large.bin"), &large).unwrap();
+        fs::write(root.join("copies/large.bin"), &large).unwrap();
+        // Same size and same first bytes, different ending
+        fs::write(root.join("almost.bin"), format!("{}y", &large[1..])).unwrap();
+        fs::write(root.join("a.txt"), "same").unwrap();
+        fs::write(root.join("b.txt"), "same").unwrap();
+        fs::write(root.join("c.txt"), "diff").unwrap();
+        fs::write(root.join("empty1"), "").unwrap();
+        fs::write(root.join("empty2"), "").unwrap();
+
+        let files = walk(&root, &WalkOptions { min_depth: 1, max_depth: None }).unwrap();
+        let refs: Vec<&File> = files.iter().collect();
+        let sets = find_duplicates(&refs, HashAlgorithm::Xxh3);
+
+        assert_eq!(sets.len(), 2);
+        let paths: Vec<&str> = sets[0].files.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(paths, vec!["copies/large.bin", "large.bin"]);
+        assert_eq!(sets[0].reclaimable(), 10_000);
+        assert_eq!(sets[1].files.len(), 2);
+        assert_eq!(sets[1].reclaimable(), 4);
+
+        let sets = find_duplicates(&refs, HashAlgorithm::Sha256);
+        assert_eq!(sets[0].hash.len(), 64);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
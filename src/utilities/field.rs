@@ -0,0 +1,404 @@
+use crate::content::HashAlgorithm;
+use crate::file::File;
+use crate::permissions::{octal_mode, symbolic_mode, SETGID, SETUID, STICKY};
+use crate::utilities::time::{format_time, now, zone};
+
+use chrono::{DateTime, Local, SecondsFormat};
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Identifies a single attribute of a `File`.
+///
+/// Fields are used wherever a query refers to a file attribute by name
+/// without attaching a condition to it, e.g. in a SELECT list or ORDER BY.
+///
+/// # Variants
+/// - `Name`: The file name
+/// - `Path`: The path relative to the analyzed directory
+/// - `Depth`: The depth below the analyzed directory
+/// - `Parent`: The directory containing the file, relative to the analyzed directory
+/// - `Stem`: The file name without its extension
+/// - `AbsPath`: The absolute path
+/// - `Extension`: The file extension
+/// - `Size`: The size in bytes
+/// - `Modified`: The last modification time
+/// - `Accessed`: The last access time
+/// - `Created`: The creation time
+/// - `Age`: The time since the last modification, in whole seconds
+/// - `FileType`: The file type (e.g., "File", "Directory", "Symlink")
+/// - `LinkTarget`: The path a symbolic link points to
+/// - `BrokenLink`: Whether the file is a symbolic link to a missing target
+/// - `Owner`: The name of the owning user
+/// - `OwnerGroup`: The name of the owning group
+/// - `Uid`: The numeric id of the owning user
+/// - `Gid`: The numeric id of the owning group
+/// - `Mode`: The permission bits in octal (e.g., "0644")
+/// - `Permissions`: The permission bits in symbolic form (e.g., "rw-r--r--")
+/// - `Setuid`: Whether the set-user-ID bit is set
+/// - `Setgid`: Whether the set-group-ID bit is set
+/// - `Sticky`: Whether the sticky bit is set
+/// - `Inode`: The inode number
+/// - `Device`: The id of the device the file resides on
+/// - `Links`: The number of hard links to the file
+/// - `DiskUsage`: The disk space allocated to the file in bytes
+/// - `Hash`: The fast XXH3 hash of the contents of a regular file, read on first use
+/// - `Sha256`: The SHA-256 hash of the contents of a regular file, read on first use
+/// - `Mime`: The MIME type sniffed from the first bytes of the contents, read on first use
+/// - `Kind`: The broad kind of content (e.g., "image", "archive"), derived from the MIME type
+/// - `Lines`: The number of lines of a text file, read on first use
+/// - `Words`: The number of whitespace-separated words of a text file, read on first use
+/// - `Chars`: The number of characters of a text file, read on first use
+/// - `IsBinary`: Whether the first bytes of a regular file look binary (see `content::is_binary`), read on first use
+/// - `IsUtf8`: Whether the contents of a regular file are valid UTF-8, read on first use
+/// - `Matches`: The total number of matches of the `contains` patterns the
+///   filter searched the file for, including zero
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Name,
+    Path,
+    Depth,
+    Parent,
+    Stem,
+    AbsPath,
+    Extension,
+    Size,
+    Modified,
+    Accessed,
+    Created,
+    Age,
+    FileType,
+    LinkTarget,
+    BrokenLink,
+    Owner,
+    OwnerGroup,
+    Uid,
+    Gid,
+    Mode,
+    Permissions,
+    Setuid,
+    Setgid,
+    Sticky,
+    Inode,
+    Device,
+    Links,
+    DiskUsage,
+    Hash,
+    Sha256,
+    Mime,
+    Kind,
+    Lines,
+    Words,
+    Chars,
+    IsBinary,
+    IsUtf8,
+    Matches,
+}
+
+/// The columns shown when no projection is requested.
+pub const DEFAULT_COLUMNS: [Field; 6] = [
+    Field::Modified,
+    Field::Accessed,
+    Field::Created,
+    Field::FileType,
+    Field::Size,
+    Field::Path,
+];
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "name" | "n" => Ok(Field::Name),
+            "path" | "p" => Ok(Field::Path),
+            "depth" | "d" => Ok(Field::Depth),
+            "parent" | "dir" => Ok(Field::Parent),
+            "stem" => Ok(Field::Stem),
+            "abs_path" | "absolute_path" => Ok(Field::AbsPath),
+            "extension" | "ext" | "e" => Ok(Field::Extension),
+            "size" | "s" => Ok(Field::Size),
+            "modified" | "mod" | "m" => Ok(Field::Modified),
+            "accessed" | "acc" | "a" => Ok(Field::Accessed),
+            "created" | "cre" | "c" => Ok(Field::Created),
+            "age" => Ok(Field::Age),
+            "filetype" | "file_type" | "type" | "f" | "t" => Ok(Field::FileType),
+            "link_target" | "target" | "link" => Ok(Field::LinkTarget),
+            "broken_link" | "broken" => Ok(Field::BrokenLink),
+            "owner" | "user" | "u" => Ok(Field::Owner),
+            "owner_group" | "group_name" | "grp" => Ok(Field::OwnerGroup),
+            "uid" => Ok(Field::Uid),
+            "gid" => Ok(Field::Gid),
+            "mode" => Ok(Field::Mode),
+            "permissions" | "perms" => Ok(Field::Permissions),
+            "setuid" | "suid" => Ok(Field::Setuid),
+            "setgid" | "sgid" => Ok(Field::Setgid),
+            "sticky" => Ok(Field::Sticky),
+            "inode" | "ino" => Ok(Field::Inode),
+            "device" | "dev" => Ok(Field::Device),
+            "nlink" | "links" | "hard_links" => Ok(Field::Links),
+            "disk_usage" | "du" => Ok(Field::DiskUsage),
+            "hash" | "xxh3" => Ok(Field::Hash),
+            "sha256" | "sha" => Ok(Field::Sha256),
+            "mime" | "mime_type" => Ok(Field::Mime),
+            "kind" => Ok(Field::Kind),
+            "lines" | "loc" => Ok(Field::Lines),
+            "words" => Ok(Field::Words),
+            "chars" | "characters" => Ok(Field::Chars),
+            "is_binary" | "binary" => Ok(Field::IsBinary),
+            "is_utf8" | "utf8" => Ok(Field::IsUtf8),
+            "matches" | "match_count" => Ok(Field::Matches),
+            _ => Err(format!("Unknown field: {}", s)),
+        }
+    }
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Field::Name => "Name",
+            Field::Path => "Path",
+            Field::Depth => "Depth",
+            Field::Parent => "Parent",
+            Field::Stem => "Stem",
+            Field::AbsPath => "Absolute Path",
+            Field::Extension => "Extension",
+            Field::Size => "Size",
+            Field::Modified => "Modified",
+            Field::Accessed => "Accessed",
+            Field::Created => "Created",
+            Field::Age => "Age (s)",
+            Field::FileType => "Type",
+            Field::LinkTarget => "Link Target",
+            Field::BrokenLink => "Broken",
+            Field::Owner => "Owner",
+            Field::OwnerGroup => "Group",
+            Field::Uid => "UID",
+            Field::Gid => "GID",
+            Field::Mode => "Mode",
+            Field::Permissions => "Permissions",
+            Field::Setuid => "Setuid",
+            Field::Setgid => "Setgid",
+            Field::Sticky => "Sticky",
+            Field::Inode => "Inode",
+            Field::Device => "Device",
+            Field::Links => "Links",
+            Field::DiskUsage => "Disk Usage",
+            Field::Hash => "Hash",
+            Field::Sha256 => "SHA-256",
+            Field::Mime => "MIME Type",
+            Field::Kind => "Kind",
+            Field::Lines => "Lines",
+            Field::Words => "Words",
+            Field::Chars => "Chars",
+            Field::IsBinary => "Binary",
+            Field::IsUtf8 => "UTF-8",
+            Field::Matches => "Matches",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Field {
+    /// The stable machine-readable name of this field, used as the key in
+    /// structured output formats.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Field::Name => "name",
+            Field::Path => "path",
+            Field::Depth => "depth",
+            Field::Parent => "parent",
+            Field::Stem => "stem",
+            Field::AbsPath => "abs_path",
+            Field::Extension => "extension",
+            Field::Size => "size",
+            Field::Modified => "modified",
+            Field::Accessed => "accessed",
+            Field::Created => "created",
+            Field::Age => "age",
+            Field::FileType => "file_type",
+            Field::LinkTarget => "link_target",
+            Field::BrokenLink => "broken_link",
+            Field::Owner => "owner",
+            Field::OwnerGroup => "owner_group",
+            Field::Uid => "uid",
+            Field::Gid => "gid",
+            Field::Mode => "mode",
+            Field::Permissions => "permissions",
+            Field::Setuid => "setuid",
+            Field::Setgid => "setgid",
+            Field::Sticky => "sticky",
+            Field::Inode => "inode",
+            Field::Device => "device",
+            Field::Links => "nlink",
+            Field::DiskUsage => "disk_usage",
+            Field::Hash => "hash",
+            Field::Sha256 => "sha256",
+            Field::Mime => "mime",
+            Field::Kind => "kind",
+            Field::Lines => "lines",
+            Field::Words => "words",
+            Field::Chars => "chars",
+            Field::IsBinary => "is_binary",
+            Field::IsUtf8 => "is_utf8",
+            Field::Matches => "matches",
+        }
+    }
+
+    /// Extracts the value of this field from a file.
+    pub fn value(&self, file: &File) -> Value {
+        match self {
+            Field::Name => Value::Text(file.name.clone()),
+            Field::Path => Value::Text(file.path.clone()),
+            Field::Depth => Value::Integer(file.depth as u64),
+            Field::Parent => Value::Text(file.parent(None)),
+            Field::Stem => Value::Text(file.stem()),
+            Field::AbsPath => Value::Text(file.abs_path()),
+            Field::Extension => Value::Text(file.extension.clone()),
+            Field::Size => Value::Integer(file.size),
+            Field::Modified => Value::Time(file.modified),
+            Field::Accessed => Value::Time(file.accessed),
+            Field::Created => Value::Time(file.created),
+            Field::Age => Value::Integer((now() - file.modified).num_seconds().max(0) as u64),
+            Field::FileType => Value::Text(file.file_type.to_string()),
+            Field::LinkTarget => file.link_target.clone().map_or(Value::Null, Value::Text),
+            Field::BrokenLink => Value::Boolean(file.broken_link),
+            Field::Owner => Value::Text(file.owner.clone()),
+            Field::OwnerGroup => Value::Text(file.group.clone()),
+            Field::Uid => Value::Integer(file.uid as u64),
+            Field::Gid => Value::Integer(file.gid as u64),
+            Field::Mode => Value::Text(octal_mode(file.mode)),
+            Field::Permissions => Value::Text(symbolic_mode(file.mode)),
+            Field::Setuid => Value::Boolean(file.mode & SETUID != 0),
+            Field::Setgid => Value::Boolean(file.mode & SETGID != 0),
+            Field::Sticky => Value::Boolean(file.mode & STICKY != 0),
+            Field::Inode => Value::Integer(file.inode),
+            Field::Device => Value::Integer(file.device),
+            Field::Links => Value::Integer(file.nlink),
+            Field::DiskUsage => Value::Integer(file.disk_usage),
+            Field::Hash => file.hash(HashAlgorithm::Xxh3).map_or(Value::Null, |hash| Value::Text(hash.to_string())),
+            Field::Sha256 => file.hash(HashAlgorithm::Sha256).map_or(Value::Null, |hash| Value::Text(hash.to_string())),
+            Field::Mime => file.mime().map_or(Value::Null, |mime| Value::Text(mime.to_string())),
+            Field::Kind => file.kind().map_or(Value::Null, |kind| Value::Text(kind.to_string())),
+            Field::Lines => file.text().map_or(Value::Null, |stats| Value::Integer(stats.lines)),
+            Field::Words => file.text().map_or(Value::Null, |stats| Value::Integer(stats.words)),
+            Field::Chars => file.text().map_or(Value::Null, |stats| Value::Integer(stats.chars)),
+            Field::IsBinary => file.stats().map_or(Value::Null, |stats| Value::Boolean(stats.is_binary)),
+            Field::IsUtf8 => file.stats().map_or(Value::Null, |stats| Value::Boolean(stats.is_utf8)),
+            Field::Matches => file.content.matches().map_or(Value::Null, Value::Integer),
+        }
+    }
+
+    /// Whether values of this field are right-aligned in listings.
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Field::Size
+                | Field::Age
+                | Field::Depth
+                | Field::Uid
+                | Field::Gid
+                | Field::Inode
+                | Field::Device
+                | Field::Links
+                | Field::DiskUsage
+                | Field::Lines
+                | Field::Words
+                | Field::Chars
+                | Field::Matches
+        )
+    }
+
+    /// Whether values of this field are byte counts, shown with units by `--human`.
+    pub fn is_size(&self) -> bool {
+        matches!(self, Field::Size | Field::DiskUsage)
+    }
+}
+
+/// Parses a comma-separated list of fields, e.g. `name,size,modified`.
+///
+/// # Errors
+///
+/// Returns an error naming the first unknown field, or if the list is empty.
+pub fn parse_columns(s: &str) -> Result<Vec<Field>, String> {
+    let columns = s
+        .split(',')
+        .filter(|part| !part.trim().is_empty())
+        .map(Field::from_str)
+        .collect::<Result<Vec<_>, _>>()?;
+    if columns.is_empty() {
+        return Err("Expected at least one column".to_string());
+    }
+    Ok(columns)
+}
+
+/// A single typed value produced by a field or an aggregate.
+///
+/// Values of the same variant are ordered naturally; values of different
+/// variants are ordered by variant, which only matters when a column mixes
+/// them (in practice only with `Null`, which sorts first).
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum Value {
+    /// No value, e.g. the inner key parts of a subtotal row
+    Null,
+    Boolean(bool),
+    Text(String),
+    Integer(u64),
+    Float(f64),
+    Time(DateTime<Local>),
+}
+
+impl Value {
+    /// Orders two values, treating incomparable floats (NaN) as equal.
+    pub fn compare(&self, other: &Value) -> Ordering {
+        self.partial_cmp(other).unwrap_or(Ordering::Equal)
+    }
+
+    /// Renders the value for machine consumption: numbers without rounding
+    /// and timestamps in ISO 8601 (RFC 3339) format with their UTC offset,
+    /// in the zone set by `--tz`.
+    pub fn to_plain_string(&self) -> String {
+        match self {
+            Value::Null => String::new(),
+            Value::Boolean(flag) => flag.to_string(),
+            Value::Text(text) => text.clone(),
+            Value::Integer(number) => number.to_string(),
+            Value::Float(number) => number.to_string(),
+            Value::Time(time) => zone().convert(*time).to_rfc3339_opts(SecondsFormat::Secs, false),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Render into a string first so width and alignment flags apply
+        let text = match self {
+            Value::Null => String::new(),
+            Value::Boolean(flag) => flag.to_string(),
+            Value::Text(text) => text.clone(),
+            Value::Integer(number) => number.to_string(),
+            Value::Float(number) => format!("{:.2}", number),
+            Value::Time(time) => format_time(*time),
+        };
+        f.pad(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_columns() {
+        assert_eq!(parse_columns("name, size,mod").unwrap(), vec![Field::Name, Field::Size, Field::Modified]);
+        assert!(parse_columns("name,colour").is_err());
+        assert!(parse_columns(" , ").is_err());
+    }
+
+    #[test]
+    fn test_value_display_pads() {
+        assert_eq!(format!("{:>5}", Value::Integer(42)), "   42");
+        assert_eq!(format!("{:<4}|", Value::Text("ab".to_string())), "ab  |");
+        assert_eq!(Value::Float(2.0 / 3.0).to_string(), "0.67");
+    }
+}
@@ -1,8 +1,13 @@
-use regex::Regex;
-use crate::file::File;
+use regex::{bytes, Regex};
+use crate::content::HashAlgorithm;
+use crate::file::{File, FileType};
+use crate::permissions::{parse_mode_mask, SETGID, SETUID, STICKY};
+use crate::utilities::size::parse_size;
+use crate::utilities::time::{now, parse_duration, parse_time, TimeRange};
 
-use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, TimeZone};
+use chrono::TimeDelta;
 use clap::ValueEnum;
+use std::fmt::Display;
 use std::str::FromStr;
 
 
@@ -27,12 +32,12 @@ impl FromStr for Comparison {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
-            "eq" | "equal" | "equals" => Ok(Comparison::Eq),
-            "ne" | "not_equal" | "neq" => Ok(Comparison::Ne),
-            "gt" | "greater" | "greater_than" => Ok(Comparison::Gt),
-            "ge" | "gte" | "greater_equal" => Ok(Comparison::Ge),
-            "lt" | "less" | "less_than" => Ok(Comparison::Lt),
-            "le" | "lte" | "less_equal" => Ok(Comparison::Le),
+            "eq" | "equal" | "equals" | "=" | "==" => Ok(Comparison::Eq),
+            "ne" | "not_equal" | "neq" | "!=" | "<>" => Ok(Comparison::Ne),
+            "gt" | "greater" | "greater_than" | ">" => Ok(Comparison::Gt),
+            "ge" | "gte" | "greater_equal" | ">=" => Ok(Comparison::Ge),
+            "lt" | "less" | "less_than" | "<" => Ok(Comparison::Lt),
+            "le" | "lte" | "less_equal" | "<=" => Ok(Comparison::Le),
             _ => Err(format!("Invalid comparison operator: {}", s)),
         }
     }
@@ -55,6 +60,45 @@ impl Comparison {
     }
 }
 
+/// Checks a MIME type against a lowercase pattern.
+///
+/// A full type with parameters (`text/plain; charset=utf-8`) must match
+/// exactly, a type without parameters (`text/plain`) ignores the parameters
+/// of `mime`, and a bare top-level type (`image` or `image/*`) matches every
+/// subtype.
+fn mime_matches(mime: &str, pattern: &str) -> bool {
+    let essence = mime.split(';').next().unwrap_or_default().trim();
+    if pattern.contains(';') {
+        mime.replace(' ', "") == pattern.replace(' ', "")
+    } else if let Some(top_level) = pattern.strip_suffix("/*").or((!pattern.contains('/')).then_some(pattern)) {
+        essence.split('/').next() == Some(top_level)
+    } else {
+        essence == pattern
+    }
+}
+
+/// Compiles a path or name pattern as a regular expression, falling back to
+/// an exact match if it is not a valid one, like `Predicate::Name` does.
+fn path_pattern(pattern: &str) -> Regex {
+    Regex::new(pattern)
+        .or_else(|_| Regex::new(&format!("^{}$", regex::escape(pattern))))
+        .expect("an escaped pattern is a valid regular expression")
+}
+
+/// Parses a boolean value written as `true`/`false`, `yes`/`no` or `1`/`0`.
+fn parse_bool(flag: &str) -> Result<bool, String> {
+    match flag {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => Err(format!("Invalid boolean value: {}", flag)),
+    }
+}
+
+/// Parses a non-negative whole number.
+fn parse_number(number: &str) -> Result<u64, String> {
+    number.parse::<u64>().map_err(|_| format!("Invalid number: {}", number))
+}
+
 /// Defines various filtering predicates for files.
 ///
 /// This enum represents different criteria that can be used to filter files.
@@ -64,11 +108,40 @@ impl Comparison {
 /// # Variants
 /// - `Name(String)`: Filter by file name using exact match or regex pattern
 /// - `Extension(String)`: Filter by file extension (exact match)
+/// - `Path(Regex)`: Filter by path relative to the analyzed directory, like `Name`
+/// - `AbsPath(Regex)`: Filter by absolute path, like `Name`
+/// - `Stem(Regex)`: Filter by file name without extension, like `Name`
+/// - `Parent(String)`: Filter by containing directory relative to the analyzed
+///   directory (exact match, `.` for entries directly inside it)
+/// - `Depth(usize, Comparison)`: Filter by depth below the analyzed directory with comparison operator
 /// - `Size(u64, Comparison)`: Filter by file size with comparison operator
-/// - `Modified(DateTime<Local>, Comparison)`: Filter by modification time with comparison
-/// - `Accessed(DateTime<Local>, Comparison)`: Filter by access time with comparison
-/// - `Created(DateTime<Local>, Comparison)`: Filter by creation time with comparison
-/// - `FileType(String)`: Filter by file type (e.g., "File", "Directory")
+/// - `Modified(TimeRange, Comparison)`: Filter by modification time with comparison
+/// - `Accessed(TimeRange, Comparison)`: Filter by access time with comparison
+/// - `Created(TimeRange, Comparison)`: Filter by creation time with comparison
+/// - `Age(TimeDelta, Comparison)`: Filter by time since the last modification with comparison
+/// - `FileType(FileType)`: Filter by file type (e.g., file, directory, symlink, fifo)
+/// - `BrokenLink(bool)`: Filter by whether the file is a symbolic link to a missing target
+/// - `Owner(String)`: Filter by owning user, given by name or numeric id
+/// - `OwnerGroup(String)`: Filter by owning group, given by name or numeric id
+/// - `Uid(u32, Comparison)`: Filter by numeric user id with comparison operator
+/// - `Gid(u32, Comparison)`: Filter by numeric group id with comparison operator
+/// - `Mode(u32, Comparison)`: Filter by permission bits, given in octal, with comparison operator
+/// - `Inode(u64, Comparison)`: Filter by inode number with comparison operator
+/// - `Device(u64, Comparison)`: Filter by device id with comparison operator
+/// - `Links(u64, Comparison)`: Filter by hard link count with comparison operator
+/// - `DiskUsage(u64, Comparison)`: Filter by allocated disk space in bytes with comparison operator
+/// - `Hash(HashAlgorithm, String)`: Filter by content hash (exact match)
+/// - `Mime(String)`: Filter by sniffed MIME type; see `mime_matches` for the accepted patterns
+/// - `Kind(String)`: Filter by broad kind of content, e.g. `image` or `archive`
+/// - `Lines(u64, Comparison)`: Filter text files by line count with comparison operator
+/// - `Words(u64, Comparison)`: Filter text files by word count with comparison operator
+/// - `Chars(u64, Comparison)`: Filter text files by character count with comparison operator
+/// - `IsBinary(bool)`: Filter regular files by whether they contain a NUL byte
+/// - `IsUtf8(bool)`: Filter regular files by whether their contents are valid UTF-8
+/// - `Contains(Regex, bool)`: Filter regular files by whether any line of their
+///   contents matches a pattern; binary files are skipped unless the flag is set
+/// - `ModeHas(u32, bool)`: Filter by whether all bits of a permission mask are set
+///   (`true`) or not all of them are (`false`), e.g. `mode has o+w` or `setuid = true`
 ///
 /// # Name Filtering Behavior
 ///
@@ -79,89 +152,578 @@ impl Comparison {
 pub enum Predicate {
     Name(String),
     Extension(String),
+    Path(Regex),
+    AbsPath(Regex),
+    Stem(Regex),
+    Parent(String),
+    Depth(usize, Comparison),
     Size(u64, Comparison),
-    Modified(DateTime<Local>, Comparison),
-    Accessed(DateTime<Local>, Comparison),
-    Created(DateTime<Local>, Comparison),
-    FileType(String),
+    Modified(TimeRange, Comparison),
+    Accessed(TimeRange, Comparison),
+    Created(TimeRange, Comparison),
+    Age(TimeDelta, Comparison),
+    FileType(FileType),
+    BrokenLink(bool),
+    Owner(String),
+    OwnerGroup(String),
+    Uid(u32, Comparison),
+    Gid(u32, Comparison),
+    Mode(u32, Comparison),
+    ModeHas(u32, bool),
+    Inode(u64, Comparison),
+    Device(u64, Comparison),
+    Links(u64, Comparison),
+    DiskUsage(u64, Comparison),
+    Hash(HashAlgorithm, String),
+    Mime(String),
+    Kind(String),
+    Lines(u64, Comparison),
+    Words(u64, Comparison),
+    Chars(u64, Comparison),
+    IsBinary(bool),
+    IsUtf8(bool),
+    Contains(bytes::Regex, bool),
 }
 
 impl FromStr for Predicate {
     type Err = String;
+
+    /// Parses a predicate written either as `field,operator,value` or as
+    /// `field operator value`. The operator may be omitted, in which case
+    /// equality is assumed (e.g. `name,test*` or `ext rs`).
     fn from_str(s: &str) -> Result<Predicate, Self::Err> {
-        let mut parts: Vec<String> = s.splitn(3, ',').map(|part| part.trim().to_lowercase()).collect();
-        
-        if parts.len() == 2 {
-            parts.insert(1, "eq".to_string());
-        }
+        let parts: Vec<&str> = if s.contains(',') {
+            s.splitn(3, ',').collect()
+        } else {
+            let (field, rest) = s.trim().split_once(char::is_whitespace).unwrap_or((s.trim(), ""));
+            match rest.trim().split_once(char::is_whitespace) {
+                Some((operator, value)) => vec![field, operator, value],
+                None if rest.trim().is_empty() => vec![field],
+                None => vec![field, rest],
+            }
+        };
 
-        if parts.len() != 3 {
-            return Err(format!(
+        match parts.as_slice() {
+            [field, value] => Predicate::from_parts(field, "eq", value),
+            [field, operator, value] => Predicate::from_parts(field, operator, value),
+            _ => Err(format!(
                 "Invalid predicate format. Expected: field,operator,value, got: {}",
                 s
-            ));
+            )),
         }
+    }
+}
 
-        let operator = parts[1]
-            .parse::<Comparison>()
-            .map_err(|_| format!("Invalid operator: {}", parts[1]))?;
+impl Predicate {
+    /// Builds a predicate from its already separated field, operator and value.
+    ///
+    /// Besides the named operators accepted by `Comparison`, `~` is accepted as
+    /// an alias for equality, which reads naturally for the regex-based `name`
+    /// predicate (e.g. `name ~ ^test`), and `has` tests permission bits of the
+    /// `mode` field against a mask in octal or `chmod` notation (e.g. `mode has o+w`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the field or operator is unknown, the operator is
+    /// not supported for the field, or the value cannot be parsed.
+    pub fn from_parts(field: &str, operator: &str, value: &str) -> Result<Predicate, String> {
+        let field = field.trim().to_lowercase();
+        let operator = operator.trim().to_lowercase();
+        let value_raw = value.trim();
+        let value = value_raw.to_lowercase();
 
-        let parse_datetime = |date_str: &str| {
-            if let Ok(naive_dt) = NaiveDateTime::parse_from_str(date_str, "%d.%m.%Y %H:%M") {
-                return Ok(Local.from_local_datetime(&naive_dt)
-                    .single()
-                    .ok_or_else(|| "Ambiguous or invalid local datetime".to_string())?);
-            }
-            // Try time only, use today's date
-            if let Ok(naive_time) = NaiveTime::parse_from_str(date_str, "%H:%M") {
-                let today = Local::now().date_naive();
-                let naive_dt = NaiveDateTime::new(today, naive_time);
-                return Ok(Local.from_local_datetime(&naive_dt)
-                    .single()
-                    .ok_or_else(|| "Ambiguous or invalid local datetime".to_string())?);
-            }
-            Err(format!("Invalid date/time format: {}", s))
-        };
+        if let "contains" | "contains_binary" = field.as_str() {
+            // Patterns are case-sensitive, so they keep their original case
+            let pattern = match operator.as_str() {
+                "~" => value_raw.to_string(),
+                _ if matches!(operator.parse::<Comparison>(), Ok(Comparison::Eq)) => regex::escape(value_raw),
+                _ => return Err(format!("The {} field only supports '=' and '~', got: {}", field, operator)),
+            };
+            let regex = bytes::Regex::new(&pattern).map_err(|e| format!("Invalid pattern {}: {}", value_raw, e))?;
+            return Ok(Predicate::Contains(regex, field == "contains_binary"));
+        }
 
+        if operator == "has" {
+            return match field.as_str() {
+                "mode" => Ok(Predicate::ModeHas(parse_mode_mask(&value)?, true)),
+                _ => Err(format!("The 'has' operator only applies to mode, got: {}", field)),
+            };
+        }
+
+        let comparison = if operator == "~" {
+            Comparison::Eq
+        } else {
+            operator
+                .parse::<Comparison>()
+                .map_err(|_| format!("Invalid operator: {}", operator))?
+        };
 
-        match (parts[0].as_str(), operator, parts[2].as_str()) {
+        match (field.as_str(), comparison, value.as_str()) {
             ("name" | "n", Comparison::Eq, name) => Ok(Predicate::Name(name.to_string())),
             ("extension" | "ext" | "e", Comparison::Eq, ext) => Ok(Predicate::Extension(ext.to_string())),
+            // Paths are case-sensitive, so they keep their original case
+            ("path" | "p", Comparison::Eq, _) => Ok(Predicate::Path(path_pattern(value_raw))),
+            ("abs_path" | "absolute_path", Comparison::Eq, _) => Ok(Predicate::AbsPath(path_pattern(value_raw))),
+            ("stem", Comparison::Eq, _) => Ok(Predicate::Stem(path_pattern(value_raw))),
+            ("parent" | "dir", Comparison::Eq, _) => {
+                Ok(Predicate::Parent(value_raw.trim_end_matches('/').replace('\\', "/")))
+            }
+            ("depth" | "d", operator, number) => {
+                let depth = number.parse::<usize>().map_err(|_| format!("Invalid number: {}", number))?;
+                Ok(Predicate::Depth(depth, operator))
+            }
             ("size" | "s", operator, size_str) => {
-                let size = size_str.parse::<u64>().map_err(|_| format!("Invalid size value: {}", size_str))?;
+                let size = parse_size(size_str)?;
                 Ok(Predicate::Size(size, operator))
             }
             ("modified" | "mod" | "m", operator, time_str) => {
-                Ok(Predicate::Modified(parse_datetime(time_str)?, operator))
+                Ok(Predicate::Modified(parse_time(time_str)?, operator))
             }
             ("accessed" | "acc" | "a", operator, time_str) => {
-                Ok(Predicate::Accessed(parse_datetime(time_str)?, operator))
+                Ok(Predicate::Accessed(parse_time(time_str)?, operator))
             }
             ("created" | "cre" | "c", operator, time_str) => {
-                Ok(Predicate::Created(parse_datetime(time_str)?, operator))
+                Ok(Predicate::Created(parse_time(time_str)?, operator))
+            }
+            ("age", operator, duration) => Ok(Predicate::Age(parse_duration(duration)?, operator)),
+            ("filetype" | "file_type" | "type" | "f" | "t", Comparison::Eq, file_type) => Ok(Predicate::FileType(file_type.parse()?)),
+            ("broken_link" | "broken", Comparison::Eq, flag) => Ok(Predicate::BrokenLink(parse_bool(flag)?)),
+            ("owner" | "user" | "u", Comparison::Eq, owner) => Ok(Predicate::Owner(owner.to_string())),
+            ("owner_group" | "group_name" | "grp", Comparison::Eq, group) => Ok(Predicate::OwnerGroup(group.to_string())),
+            ("uid", operator, id_str) => {
+                let id = id_str.parse::<u32>().map_err(|_| format!("Invalid user id: {}", id_str))?;
+                Ok(Predicate::Uid(id, operator))
             }
-            ("filetype" | "file_type" | "type" | "f" | "t", Comparison::Eq, file_type) => Ok(Predicate::FileType(file_type.to_string())),
-            _ => Err(format!("Invalid predicate: {}", s)),
+            ("gid", operator, id_str) => {
+                let id = id_str.parse::<u32>().map_err(|_| format!("Invalid group id: {}", id_str))?;
+                Ok(Predicate::Gid(id, operator))
+            }
+            ("mode", operator, mode_str) => {
+                let mode = u32::from_str_radix(mode_str, 8)
+                    .ok()
+                    .filter(|mode| *mode <= 0o7777)
+                    .ok_or_else(|| format!("Invalid octal mode: {}", mode_str))?;
+                Ok(Predicate::Mode(mode, operator))
+            }
+            ("inode" | "ino", operator, number) => Ok(Predicate::Inode(parse_number(number)?, operator)),
+            ("device" | "dev", operator, number) => Ok(Predicate::Device(parse_number(number)?, operator)),
+            ("nlink" | "links" | "hard_links", operator, number) => Ok(Predicate::Links(parse_number(number)?, operator)),
+            ("disk_usage" | "du", operator, size_str) => {
+                let size = parse_size(size_str)?;
+                Ok(Predicate::DiskUsage(size, operator))
+            }
+            ("hash" | "xxh3", Comparison::Eq, hash) => Ok(Predicate::Hash(HashAlgorithm::Xxh3, hash.to_string())),
+            ("sha256" | "sha", Comparison::Eq, hash) => Ok(Predicate::Hash(HashAlgorithm::Sha256, hash.to_string())),
+            ("mime" | "mime_type", Comparison::Eq, mime) => Ok(Predicate::Mime(mime.to_lowercase())),
+            ("kind", Comparison::Eq, kind) => Ok(Predicate::Kind(kind.to_lowercase())),
+            ("lines" | "loc", operator, number) => Ok(Predicate::Lines(parse_number(number)?, operator)),
+            ("words", operator, number) => Ok(Predicate::Words(parse_number(number)?, operator)),
+            ("chars" | "characters", operator, number) => Ok(Predicate::Chars(parse_number(number)?, operator)),
+            ("is_binary" | "binary", Comparison::Eq, flag) => Ok(Predicate::IsBinary(parse_bool(flag)?)),
+            ("is_utf8" | "utf8", Comparison::Eq, flag) => Ok(Predicate::IsUtf8(parse_bool(flag)?)),
+            ("setuid" | "suid", Comparison::Eq, flag) => Ok(Predicate::ModeHas(SETUID, parse_bool(flag)?)),
+            ("setgid" | "sgid", Comparison::Eq, flag) => Ok(Predicate::ModeHas(SETGID, parse_bool(flag)?)),
+            ("sticky", Comparison::Eq, flag) => Ok(Predicate::ModeHas(STICKY, parse_bool(flag)?)),
+            _ => Err(format!("Invalid predicate: {},{},{}", field, operator, value)),
+        }
+    }
+
+    /// Checks whether a single file satisfies the predicate.
+    ///
+    /// See `filter` for details on how `Predicate::Name` is matched.
+    pub fn matches(&self, file: &File) -> bool {
+        match self {
+            Predicate::Name(name) => {
+                if let Ok(regex) = Regex::new(name) {
+                    regex.is_match(&file.name)
+                } else {
+                    file.name == *name
+                }
+            }
+            Predicate::Extension(extension) => file.extension == *extension,
+            Predicate::Path(regex) => regex.is_match(&file.path),
+            Predicate::AbsPath(regex) => regex.is_match(&file.abs_path()),
+            Predicate::Stem(regex) => regex.is_match(&file.stem()),
+            Predicate::Parent(parent) => file.parent(None) == *parent,
+            Predicate::Depth(depth, comparison) => comparison.compare(file.depth, *depth),
+            Predicate::Size(size, comparison) => {
+                comparison.compare(file.size, *size)
+            }
+            Predicate::Modified(time, comparison) => {
+                time.compare(file.modified, comparison)
+            }
+            Predicate::Accessed(time, comparison) => {
+                time.compare(file.accessed, comparison)
+            }
+            Predicate::Created(time, comparison) => {
+                time.compare(file.created, comparison)
+            }
+            Predicate::Age(age, comparison) => comparison.compare(now() - file.modified, *age),
+            Predicate::FileType(file_type) => file.file_type == *file_type,
+            Predicate::BrokenLink(broken) => file.broken_link == *broken,
+            Predicate::Owner(owner) => file.owner.eq_ignore_ascii_case(owner) || file.uid.to_string() == *owner,
+            Predicate::OwnerGroup(group) => file.group.eq_ignore_ascii_case(group) || file.gid.to_string() == *group,
+            Predicate::Uid(id, comparison) => comparison.compare(file.uid, *id),
+            Predicate::Gid(id, comparison) => comparison.compare(file.gid, *id),
+            Predicate::Mode(mode, comparison) => comparison.compare(file.mode, *mode),
+            Predicate::ModeHas(mask, set) => (file.mode & mask == *mask) == *set,
+            Predicate::Inode(inode, comparison) => comparison.compare(file.inode, *inode),
+            Predicate::Device(device, comparison) => comparison.compare(file.device, *device),
+            Predicate::Links(nlink, comparison) => comparison.compare(file.nlink, *nlink),
+            Predicate::DiskUsage(size, comparison) => comparison.compare(file.disk_usage, *size),
+            Predicate::Hash(algorithm, hash) => file.hash(*algorithm) == Some(hash.as_str()),
+            Predicate::Mime(pattern) => file.mime().is_some_and(|mime| mime_matches(mime, pattern)),
+            Predicate::Kind(kind) => file.kind() == Some(kind.as_str()),
+            Predicate::Lines(lines, comparison) => file.text().is_some_and(|stats| comparison.compare(stats.lines, *lines)),
+            Predicate::Words(words, comparison) => file.text().is_some_and(|stats| comparison.compare(stats.words, *words)),
+            Predicate::Chars(chars, comparison) => file.text().is_some_and(|stats| comparison.compare(stats.chars, *chars)),
+            Predicate::IsBinary(binary) => file.stats().is_some_and(|stats| stats.is_binary == *binary),
+            Predicate::IsUtf8(utf8) => file.stats().is_some_and(|stats| stats.is_utf8 == *utf8),
+            Predicate::Contains(regex, binary) => file.count_matches(regex, !binary).is_some_and(|count| count > 0),
+        }
+    }
+
+    /// Estimates how expensive the predicate is to evaluate: 0 for metadata,
+    /// 1 for content attributes that are read once and cached, and 2 for
+    /// content searches. `AND` and `OR` evaluate cheaper operands first.
+    pub fn cost(&self) -> u32 {
+        match self {
+            Predicate::Hash(..)
+            | Predicate::Mime(_)
+            | Predicate::Kind(_)
+            | Predicate::Lines(..)
+            | Predicate::Words(..)
+            | Predicate::Chars(..)
+            | Predicate::IsBinary(_)
+            | Predicate::IsUtf8(_) => 1,
+            Predicate::Contains(..) => 2,
+            _ => 0,
+        }
+    }
+}
+
+/// A single `field operator value` condition that can be combined into a `BoolExpr`.
+///
+/// Implemented by `Predicate` for WHERE conditions on files and by
+/// `GroupPredicate` for HAVING conditions on groups.
+pub trait Leaf: Sized {
+    /// What the condition is evaluated against.
+    type Subject<'a>: ?Sized;
+
+    /// The clause the condition belongs to, used in error messages.
+    const CLAUSE: &'static str;
+
+    /// Builds a condition from its already separated field, operator and value.
+    fn from_parts(field: &str, operator: &str, value: &str) -> Result<Self, String>;
+
+    /// Checks whether the subject satisfies the condition.
+    fn matches(&self, subject: &Self::Subject<'_>) -> bool;
+
+    /// Estimates the relative cost of `matches`, so cheap conditions can be
+    /// evaluated first.
+    fn cost(&self) -> u32 {
+        0
+    }
+}
+
+impl Leaf for Predicate {
+    type Subject<'a> = File;
+
+    const CLAUSE: &'static str = "WHERE";
+
+    fn from_parts(field: &str, operator: &str, value: &str) -> Result<Self, String> {
+        Predicate::from_parts(field, operator, value)
+    }
+
+    fn matches(&self, file: &File) -> bool {
+        Predicate::matches(self, file)
+    }
+
+    fn cost(&self) -> u32 {
+        Predicate::cost(self)
+    }
+}
+
+/// A WHERE expression: a boolean combination of file predicates.
+pub type Expr = BoolExpr<Predicate>;
+
+/// A boolean combination of conditions.
+///
+/// Expressions are parsed from strings such as
+/// `size > 1000 AND (ext = log OR ext = tmp) AND NOT name ~ keep`.
+/// Each leaf is a condition written as `field,operator,value` or
+/// `field operator value`, with the operator defaulting to equality.
+///
+/// # Syntax
+/// - `AND` / `&&`, `OR` / `||` and `NOT` / `!` (keywords are case-insensitive)
+/// - Parentheses for grouping
+/// - Double or single quotes around values containing spaces, parentheses or keywords
+///
+/// `NOT` binds tighter than `AND`, which binds tighter than `OR`.
+///
+/// # Variants
+/// - `Predicate(T)`: A single condition
+/// - `Not(Box<BoolExpr<T>>)`: Matches what the inner expression rejects
+/// - `And(Box<BoolExpr<T>>, Box<BoolExpr<T>>)`: Matches what both expressions accept
+/// - `Or(Box<BoolExpr<T>>, Box<BoolExpr<T>>)`: Matches what either expression accepts
+#[derive(Debug, Clone)]
+pub enum BoolExpr<T> {
+    Predicate(T),
+    Not(Box<BoolExpr<T>>),
+    And(Box<BoolExpr<T>>, Box<BoolExpr<T>>),
+    Or(Box<BoolExpr<T>>, Box<BoolExpr<T>>),
+}
+
+impl<T: Leaf> BoolExpr<T> {
+    /// Combines two expressions with `AND`, putting the cheaper one first
+    /// (see `by_cost`).
+    pub fn and(left: BoolExpr<T>, right: BoolExpr<T>) -> BoolExpr<T> {
+        let (first, second) = Self::by_cost(left, right);
+        BoolExpr::And(Box::new(first), Box::new(second))
+    }
+
+    /// Combines two expressions with `OR`, putting the cheaper one first
+    /// (see `by_cost`).
+    pub fn or(left: BoolExpr<T>, right: BoolExpr<T>) -> BoolExpr<T> {
+        let (first, second) = Self::by_cost(left, right);
+        BoolExpr::Or(Box::new(first), Box::new(second))
+    }
+
+    /// Evaluates the expression against a single subject, short-circuiting
+    /// `AND` and `OR` from left to right. Expressions built with `and` and
+    /// `or` hold their cheaper operand on the left, so e.g. metadata
+    /// conditions can rule out a file before its contents are read.
+    pub fn matches(&self, subject: &T::Subject<'_>) -> bool {
+        match self {
+            BoolExpr::Predicate(predicate) => predicate.matches(subject),
+            BoolExpr::Not(inner) => !inner.matches(subject),
+            BoolExpr::And(left, right) => left.matches(subject) && right.matches(subject),
+            BoolExpr::Or(left, right) => left.matches(subject) || right.matches(subject),
+        }
+    }
+
+    /// The cost of the most expensive condition in the expression.
+    pub fn cost(&self) -> u32 {
+        match self {
+            BoolExpr::Predicate(predicate) => predicate.cost(),
+            BoolExpr::Not(inner) => inner.cost(),
+            BoolExpr::And(left, right) | BoolExpr::Or(left, right) => left.cost().max(right.cost()),
         }
+    }
+
+    /// Orders two operands by their cost (see `Leaf::cost`), keeping them
+    /// from left to right when they cost the same.
+    fn by_cost(left: BoolExpr<T>, right: BoolExpr<T>) -> (BoolExpr<T>, BoolExpr<T>) {
+        if right.cost() < left.cost() {
+            (right, left)
+        } else {
+            (left, right)
+        }
+    }
+}
 
+impl From<Predicate> for Expr {
+    fn from(predicate: Predicate) -> Self {
+        Expr::Predicate(predicate)
+    }
+}
+
+impl<T: Leaf> FromStr for BoolExpr<T> {
+    type Err = String;
 
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s, T::CLAUSE)?;
+        let mut parser = ExprParser { tokens, position: 0 };
+        let expr = parser.parse_or()?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(expr),
+            Some(token) => Err(format!("Unexpected {} in {} expression", token, T::CLAUSE)),
+        }
     }
 }
 
-/// Filters a collection of files based on the specified predicate.
+/// A lexical unit of a WHERE or HAVING expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    /// A bare or quoted word; quoted words are never treated as keywords.
+    Word(String),
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+            Token::And => write!(f, "AND"),
+            Token::Or => write!(f, "OR"),
+            Token::Not => write!(f, "NOT"),
+            Token::Word(word) => write!(f, "'{}'", word),
+        }
+    }
+}
+
+/// Splits a WHERE or HAVING expression into tokens.
 ///
-/// This function takes a slice of file references and applies the given predicate
+/// A parenthesis opened in the middle of a bare word (e.g. the regex in
+/// `name,^(a|b)`) is kept as part of that word together with its matching
+/// closing parenthesis, so existing single-predicate filters keep working.
+fn tokenize(s: &str, clause: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut word = String::new();
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some(other) => word.push(other),
+                        None => return Err(format!("Unterminated quote in {} expression: {}", clause, s)),
+                    }
+                }
+                tokens.push(Token::Word(word));
+            }
+            _ => {
+                let mut word = String::new();
+                let mut depth = 0;
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || (c == ')' && depth == 0) {
+                        break;
+                    }
+                    if c == '(' {
+                        depth += 1;
+                    } else if c == ')' {
+                        depth -= 1;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(match word.to_lowercase().as_str() {
+                    "and" | "&&" => Token::And,
+                    "or" | "||" => Token::Or,
+                    "not" | "!" => Token::Not,
+                    _ => Token::Word(word),
+                });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser over the tokens of a WHERE or HAVING expression.
+struct ExprParser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl ExprParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn parse_or<T: Leaf>(&mut self) -> Result<BoolExpr<T>, String> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            expr = BoolExpr::or(expr, self.parse_and()?);
+        }
+        Ok(expr)
+    }
+
+    fn parse_and<T: Leaf>(&mut self) -> Result<BoolExpr<T>, String> {
+        let mut expr = self.parse_not()?;
+        while self.peek() == Some(&Token::And) {
+            self.position += 1;
+            expr = BoolExpr::and(expr, self.parse_not()?);
+        }
+        Ok(expr)
+    }
+
+    fn parse_not<T: Leaf>(&mut self) -> Result<BoolExpr<T>, String> {
+        if self.peek() == Some(&Token::Not) {
+            self.position += 1;
+            return Ok(BoolExpr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary<T: Leaf>(&mut self) -> Result<BoolExpr<T>, String> {
+        match self.peek() {
+            Some(Token::LParen) => {
+                self.position += 1;
+                let expr = self.parse_or()?;
+                if self.peek() != Some(&Token::RParen) {
+                    return Err(format!("Missing ')' in {} expression", T::CLAUSE));
+                }
+                self.position += 1;
+                Ok(expr)
+            }
+            Some(Token::Word(_)) => self.parse_predicate(),
+            Some(token) => Err(format!("Unexpected {} in {} expression", token, T::CLAUSE)),
+            None => Err(format!("Unexpected end of {} expression", T::CLAUSE)),
+        }
+    }
+
+    /// Consumes consecutive words and turns them into a single predicate.
+    fn parse_predicate<T: Leaf>(&mut self) -> Result<BoolExpr<T>, String> {
+        let mut words = Vec::new();
+        while let Some(Token::Word(word)) = self.peek() {
+            words.push(word.clone());
+            self.position += 1;
+        }
+
+        let predicate = if words[0].contains(',') {
+            // `field,operator,value`, where the value may contain spaces
+            let joined = words.join(" ");
+            match joined.splitn(3, ',').collect::<Vec<_>>().as_slice() {
+                [field, value] => T::from_parts(field, "eq", value)?,
+                [field, operator, value] => T::from_parts(field, operator, value)?,
+                _ => unreachable!("a word containing a comma splits into at least two parts"),
+            }
+        } else {
+            match words.as_slice() {
+                [field, value] => T::from_parts(field, "eq", value)?,
+                [field, operator, value @ ..] => T::from_parts(field, operator, &value.join(" "))?,
+                _ => return Err(format!("Incomplete condition in {} expression: {}", T::CLAUSE, words.join(" "))),
+            }
+        };
+        Ok(BoolExpr::Predicate(predicate))
+    }
+}
+
+/// Filters a collection of files based on the specified expression.
+///
+/// This function takes a slice of file references and applies the given expression
 /// to each file, returning only those files that satisfy the filtering condition.
 /// The function preserves the lifetime of the input references in the output.
 ///
 /// # Arguments
 ///
 /// * `files` - A slice of references to `File` objects to be filtered
-/// * `predicate` - The filtering criterion to apply to each file
+/// * `expr` - The filtering criterion to apply to each file; a plain `Predicate` is accepted as well
 ///
 /// # Returns
 ///
-/// A vector containing references to the files that satisfy the predicate condition.
+/// A vector containing references to the files that satisfy the condition.
 /// The returned references have the same lifetime as the input references.
 ///
 /// # Name Filtering Details
@@ -170,35 +732,11 @@ impl FromStr for Predicate {
 /// string as a regular expression. If successful, it uses regex matching against
 /// the file name. If regex compilation fails (due to invalid regex syntax), it
 /// falls back to exact string comparison.
-pub fn filter<'a>(files: &[&'a File], predicate: Predicate) -> Vec<&'a File> {
+pub fn filter<'a>(files: &[&'a File], expr: impl Into<Expr>) -> Vec<&'a File> {
+    let expr = expr.into();
     files
         .iter()
-        .filter(|entry_ref| {
-            let entry: &File = *entry_ref;
-            match &predicate {
-                Predicate::Name(name) => {
-                    if let Ok(regex) = Regex::new(name) {
-                        regex.is_match(&entry.name)
-                    } else {
-                        entry.name == *name
-                    }
-                }
-                Predicate::Extension(extension) => entry.extension == *extension,
-                Predicate::Size(size, comparison) => {
-                    comparison.compare(entry.size, *size)
-                }
-                Predicate::Modified(time, comparison) => {
-                    comparison.compare(entry.modified, *time)
-                }
-                Predicate::Accessed(time, comparison) => {
-                    comparison.compare(entry.accessed, *time)
-                }
-                Predicate::Created(time, comparison) => {
-                    comparison.compare(entry.created, *time)
-                }
-                Predicate::FileType(file_type) => entry.file_type == *file_type,
-            }
-        })
+        .filter(|file| expr.matches(file))
         .copied()
         .collect()
 }
@@ -207,6 +745,7 @@ pub fn filter<'a>(files: &[&'a File], predicate: Predicate) -> Vec<&'a File> {
 mod tests {
     use super::*;
     use crate::file::File;
+    use crate::utilities::field::{Field, Value};
     use chrono::{DateTime, Local, TimeZone};
 
     fn dt(secs: u64) -> DateTime<Local> {
@@ -225,12 +764,15 @@ mod tests {
     ) -> File {
         File {
             name: name.to_string(),
+            path: name.to_string(),
+            depth: 1,
             extension: extension.to_string(),
             size,
             modified: dt(modified),
             accessed: dt(accessed),
             created: dt(created),
-            file_type: file_type.to_string(),
+            file_type: file_type.parse().unwrap(),
+            ..Default::default()
         }
     }
 
@@ -267,7 +809,7 @@ mod tests {
         let files = vec![&file1, &file2];
         let result = filter(
             &files,
-            Predicate::Modified(dt(15), Comparison::Lt),
+            Predicate::Modified(dt(15).into(), Comparison::Lt),
         );
         assert_eq!(result, vec![&file1]);
     }
@@ -279,7 +821,7 @@ mod tests {
         let files = vec![&file1, &file2];
         let result = filter(
             &files,
-            Predicate::Accessed(dt(20), Comparison::Eq),
+            Predicate::Accessed(dt(20).into(), Comparison::Eq),
         );
         assert_eq!(result, vec![&file2]);
     }
@@ -291,7 +833,7 @@ mod tests {
         let files = vec![&file1, &file2];
         let result = filter(
             &files,
-            Predicate::Created(dt(10), Comparison::Ge),
+            Predicate::Created(dt(10).into(), Comparison::Ge),
         );
         assert_eq!(result, vec![&file1, &file2]);
     }
@@ -301,10 +843,44 @@ mod tests {
         let file1 = mock_file("a", "txt", 10, 0, 0, 0, "File");
         let file2 = mock_file("b", "", 0, 0, 0, 0, "Directory");
         let files = vec![&file1, &file2];
-        let result = filter(&files, Predicate::FileType("Directory".to_string()));
+        let result = filter(&files, Predicate::FileType(FileType::Directory));
         assert_eq!(result, vec![&file2]);
     }
 
+    #[test]
+    fn test_extended_file_type_predicates() {
+        let mut link = mock_file("dangling", "", 7, 0, 0, 0, "symlink");
+        link.link_target = Some("missing".to_string());
+        link.broken_link = true;
+        let pipe = mock_file("queue", "", 0, 0, 0, 0, "fifo");
+        let files = vec![&link, &pipe];
+        assert_eq!(filter(&files, Expr::from_str("type = link").unwrap()), vec![&link]);
+        assert_eq!(filter(&files, Expr::from_str("type = pipe").unwrap()), vec![&pipe]);
+        assert_eq!(filter(&files, Expr::from_str("broken = true").unwrap()), vec![&link]);
+        assert!(Expr::from_str("type = tape").is_err());
+    }
+
+    #[test]
+    fn test_ownership_and_mode_predicates() {
+        let mut shared = mock_file("shared", "", 0, 0, 0, 0, "file");
+        shared.owner = "alice".to_string();
+        shared.uid = 1000;
+        shared.mode = 0o666;
+        let mut tool = mock_file("tool", "", 0, 0, 0, 0, "file");
+        tool.owner = "root".to_string();
+        tool.mode = 0o4755;
+        let files = vec![&shared, &tool];
+        assert_eq!(filter(&files, Expr::from_str("mode has o+w").unwrap()), vec![&shared]);
+        assert_eq!(filter(&files, Expr::from_str("mode,has,u+s").unwrap()), vec![&tool]);
+        assert_eq!(filter(&files, Expr::from_str("setuid = false").unwrap()), vec![&shared]);
+        assert_eq!(filter(&files, Expr::from_str("mode = 755 OR owner = Alice").unwrap()), vec![&shared]);
+        assert_eq!(filter(&files, Expr::from_str("mode = 4755").unwrap()), vec![&tool]);
+        assert_eq!(filter(&files, Expr::from_str("uid >= 1000").unwrap()), vec![&shared]);
+        assert_eq!(filter(&files, Expr::from_str("user 0").unwrap()), vec![&tool]);
+        assert!(Expr::from_str("size has o+w").is_err());
+        assert!(Expr::from_str("mode = 999").is_err());
+    }
+
     #[test]
     fn test_name_predicate_regex_match() {
         let file = mock_file("report.txt", "txt", 100, 0, 0, 0, "File");
@@ -336,4 +912,233 @@ mod tests {
         let result = filter(&files, Predicate::Name("re[port.txt".to_string()));
         assert_eq!(result.len(), 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_predicate_whitespace_form() {
+        let predicate = Predicate::from_str("size >= 15").unwrap();
+        assert!(matches!(predicate, Predicate::Size(15, Comparison::Ge)));
+        let predicate = Predicate::from_str("size > 1.5MiB").unwrap();
+        assert!(matches!(predicate, Predicate::Size(1_572_864, Comparison::Gt)));
+        let predicate = Predicate::from_str("du <= 2gb").unwrap();
+        assert!(matches!(predicate, Predicate::DiskUsage(2_000_000_000, Comparison::Le)));
+        assert!(Predicate::from_str("size > 10 XB").is_err());
+        let predicate = Predicate::from_str("ext rs").unwrap();
+        assert!(matches!(predicate, Predicate::Extension(ext) if ext == "rs"));
+    }
+
+    #[test]
+    fn test_expr_precedence() {
+        let log = mock_file("a.log", "log", 2000, 0, 0, 0, "File");
+        let tmp = mock_file("b.tmp", "tmp", 10, 0, 0, 0, "File");
+        let rs = mock_file("c.rs", "rs", 2000, 0, 0, 0, "File");
+        let files = vec![&log, &tmp, &rs];
+        // AND binds tighter than OR
+        let expr = Expr::from_str("ext = tmp OR size > 1000 AND ext = log").unwrap();
+        assert_eq!(filter(&files, expr), vec![&log, &tmp]);
+    }
+
+    #[test]
+    fn test_expr_parentheses_and_not() {
+        let log = mock_file("a.log", "log", 2000, 0, 0, 0, "File");
+        let keep = mock_file("keep.log", "log", 2000, 0, 0, 0, "File");
+        let tmp = mock_file("b.tmp", "tmp", 10, 0, 0, 0, "File");
+        let files = vec![&log, &keep, &tmp];
+        let expr = Expr::from_str("size > 1000 AND (ext = log OR ext = tmp) AND NOT name ~ keep").unwrap();
+        assert_eq!(filter(&files, expr), vec![&log]);
+    }
+
+    #[test]
+    fn test_expr_legacy_comma_form() {
+        let file1 = mock_file("a.txt", "txt", 10, 0, 0, 0, "File");
+        let file2 = mock_file("b.txt", "txt", 20, 0, 0, 0, "File");
+        let files = vec![&file1, &file2];
+        let expr = Expr::from_str("size,gt,15").unwrap();
+        assert_eq!(filter(&files, expr), vec![&file2]);
+        let expr = Expr::from_str("name,^(a|c)").unwrap();
+        assert_eq!(filter(&files, expr), vec![&file1]);
+    }
+
+    #[test]
+    fn test_expr_quoted_value() {
+        let file1 = mock_file("and", "", 10, 0, 0, 0, "File");
+        let file2 = mock_file("b.txt", "txt", 20, 0, 0, 0, "File");
+        let files = vec![&file1, &file2];
+        let expr = Expr::from_str("name ~ \"^and$\" || size < 5").unwrap();
+        assert_eq!(filter(&files, expr), vec![&file1]);
+    }
+
+    #[test]
+    fn test_expr_errors() {
+        assert!(Expr::from_str("(size > 5").is_err());
+        assert!(Expr::from_str("size > 5 AND").is_err());
+        assert!(Expr::from_str("size > 5)").is_err());
+        assert!(Expr::from_str("name ~ 'open").is_err());
+        assert!(Expr::from_str("").is_err());
+    }
+
+    #[test]
+    fn test_mime_matches() {
+        let mime = "text/plain; charset=utf-8";
+        assert!(mime_matches(mime, "text/plain"));
+        assert!(mime_matches(mime, "text"));
+        assert!(mime_matches(mime, "text/*"));
+        assert!(mime_matches(mime, "text/plain;charset=utf-8"));
+        assert!(!mime_matches(mime, "text/plain; charset=us-ascii"));
+        assert!(!mime_matches(mime, "text/html"));
+        assert!(!mime_matches("image/png", "text"));
+    }
+
+    #[test]
+    fn test_mime_and_kind_predicates() {
+        let root = std::env::temp_dir().join(format!("lsdir-filter-mime-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("dir")).unwrap();
+        // Misnamed and extensionless files are recognized by their contents
+        std::fs::write(root.join("picture.txt"), b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR").unwrap();
+        std::fs::write(root.join("README"), "plain text\n").unwrap();
+
+        let files = crate::walk::walk(&root, &crate::walk::WalkOptions::default()).unwrap();
+        let refs: Vec<&File> = files.iter().collect();
+        let names = |expr: &str| -> Vec<String> {
+            let mut names: Vec<String> = filter(&refs, Expr::from_str(expr).unwrap())
+                .iter()
+                .map(|file| file.name.clone())
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names("mime = image/png"), vec!["picture.txt"]);
+        assert_eq!(names("kind = image"), vec!["picture.txt"]);
+        assert_eq!(names("mime = text"), vec!["README"]);
+        assert_eq!(names("kind = directory"), vec!["dir"]);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_contains_predicate() {
+        let root = std::env::temp_dir().join(format!("lsdir-filter-contains-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("main.rs"), "fn main() {\n    // TODO: Fix (a+b)\n}\n").unwrap();
+        std::fs::write(root.join("notes.txt"), "todo in lower case\n").unwrap();
+        std::fs::write(root.join("data.bin"), b"\x00TODO\n").unwrap();
+
+        let files = crate::walk::walk(&root, &crate::walk::WalkOptions::default()).unwrap();
+        let refs: Vec<&File> = files.iter().collect();
+        let names = |expr: &str| -> Vec<String> {
+            let mut names: Vec<String> = filter(&refs, Expr::from_str(expr).unwrap())
+                .iter()
+                .map(|file| file.name.clone())
+                .collect();
+            names.sort();
+            names
+        };
+        // Literal patterns are case-sensitive and have no special characters
+        assert_eq!(names("contains = TODO"), vec!["main.rs"]);
+        assert_eq!(names("contains = '(a+b)'"), vec!["main.rs"]);
+        assert_eq!(names("contains ~ '(?i)^todo'"), vec!["notes.txt"]);
+        assert_eq!(names("contains_binary = TODO"), vec!["data.bin", "main.rs"]);
+        assert!(Expr::from_str("contains > TODO").is_err());
+        assert!(Expr::from_str("contains ~ '(unclosed'").is_err());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_cheap_predicates_short_circuit() {
+        let root = std::env::temp_dir().join(format!("lsdir-filter-short-circuit-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("small.txt"), "TODO TODO\n").unwrap();
+
+        // The size condition is moved first, so it rules the file out before its contents are searched
+        let files = crate::walk::walk(&root, &crate::walk::WalkOptions::default()).unwrap();
+        let expr = Expr::from_str("contains = TODO AND size > 1000").unwrap();
+        assert!(matches!(&expr, Expr::And(left, _) if matches!(**left, Expr::Predicate(Predicate::Size(..)))));
+        assert!(!expr.matches(&files[0]));
+        let expr = Expr::from_str("contains = TODO OR kind = text OR size > 1000").unwrap();
+        let Expr::Or(first, rest) = &expr else { unreachable!() };
+        assert!(matches!(**first, Expr::Predicate(Predicate::Size(..))));
+        assert!(matches!(&**rest, Expr::Or(second, _) if matches!(**second, Expr::Predicate(Predicate::Kind(_)))));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_matches_total_searched_patterns() {
+        let root = std::env::temp_dir().join(format!("lsdir-filter-matches-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("notes.txt"), "TODO TODO FIXME\n").unwrap();
+        std::fs::write(root.join("plain.txt"), "nothing to do\n").unwrap();
+        let files = crate::walk::walk(&root, &crate::walk::WalkOptions::default()).unwrap();
+        let refs: Vec<&File> = files.iter().collect();
+        let matches = |files: &[&File]| -> Vec<(String, Value)> {
+            files.iter().map(|file| (file.name.clone(), Field::Matches.value(file))).collect()
+        };
+
+        // Counts of every searched pattern add up, including zero counts
+        let filtered = filter(&refs, Expr::from_str("contains = TODO AND contains = FIXME OR contains = nothing").unwrap());
+        assert_eq!(
+            matches(&filtered),
+            vec![("notes.txt".to_string(), Value::Integer(3)), ("plain.txt".to_string(), Value::Integer(1))]
+        );
+
+        // A pattern is searched and counted once per file
+        let files = crate::walk::walk(&root, &crate::walk::WalkOptions::default()).unwrap();
+        let refs: Vec<&File> = files.iter().collect();
+        let filtered = filter(&refs, Expr::from_str("contains = TODO AND (contains = TODO OR size > 1000)").unwrap());
+        assert_eq!(matches(&filtered), vec![("notes.txt".to_string(), Value::Integer(2))]);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_path_predicates() {
+        let mut main = mock_file("main.rs", "rs", 10, 0, 0, 0, "File");
+        main.path = "src/main.rs".to_string();
+        main.depth = 2;
+        let mut field = mock_file("field.rs", "rs", 10, 0, 0, 0, "File");
+        field.path = "src/utilities/field.rs".to_string();
+        field.depth = 3;
+        let readme = mock_file("README.md", "md", 10, 0, 0, 0, "File");
+        let files = vec![&main, &field, &readme];
+        let names = |expr: &str| -> Vec<String> {
+            filter(&files, Expr::from_str(expr).unwrap())
+                .iter()
+                .map(|file| file.name.clone())
+                .collect()
+        };
+
+        // Path patterns are regular expressions and case-sensitive
+        assert_eq!(names("path ~ ^src/utilities/"), vec!["field.rs"]);
+        assert_eq!(names("path ~ README"), vec!["README.md"]);
+        assert!(names("path ~ readme").is_empty());
+        assert_eq!(names("parent = src"), vec!["main.rs"]);
+        assert_eq!(names("parent = src/utilities/"), vec!["field.rs"]);
+        assert_eq!(names("parent = ."), vec!["README.md"]);
+        assert_eq!(names("stem = ^(main|README)$"), vec!["main.rs", "README.md"]);
+        assert_eq!(names("depth >= 2"), vec!["main.rs", "field.rs"]);
+        assert!(Expr::from_str("depth > deep").is_err());
+    }
+
+    #[test]
+    fn test_date_literals_and_age() {
+        let recent = mock_file("recent.log", "log", 10, 0, 0, 0, "File");
+        let recent = File { modified: now() - TimeDelta::hours(2), ..recent };
+        let old = mock_file("old.log", "log", 10, 0, 0, 0, "File");
+        let old = File { modified: now() - TimeDelta::days(30), ..old };
+        let files = vec![&recent, &old];
+        let names = |expr: &str| -> Vec<String> {
+            filter(&files, Expr::from_str(expr).unwrap())
+                .iter()
+                .map(|file| file.name.clone())
+                .collect()
+        };
+
+        assert_eq!(names("age < 24h"), vec!["recent.log"]);
+        assert_eq!(names("age >= '3 weeks'"), vec!["old.log"]);
+        assert_eq!(names("modified > 7d"), vec!["recent.log"]);
+        assert_eq!(names("modified < '3 weeks ago'"), vec!["old.log"]);
+        assert_eq!(names("modified >= 1970-01-02"), vec!["recent.log", "old.log"]);
+        assert!(Expr::from_str("age < soon").is_err());
+        assert!(Expr::from_str("modified > someday").is_err());
+    }
+}
@@ -1,3 +1,8 @@
 pub mod aggregate;
+pub mod duplicates;
+pub mod field;
 pub mod filter;
-pub mod group;
\ No newline at end of file
+pub mod group;
+pub mod order;
+pub mod size;
+pub mod time;
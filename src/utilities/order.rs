@@ -0,0 +1,335 @@
+use crate::file::File;
+use crate::utilities::aggregate::AggregateFunction;
+use crate::utilities::field::{Field, Value};
+use crate::utilities::group::{key_parts, rollup, GroupingOperator, KEY_SEPARATOR};
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Defines what a single sort key orders by.
+///
+/// # Variants
+/// - `Field(Field)`: Order files by one of their fields; orders groups by their
+///   key when the field is the one the files were grouped by
+/// - `Aggregate(AggregateFunction)`: Order groups by the value of an aggregate
+///   computed over each group
+#[derive(Debug, Clone)]
+pub enum OrderTarget {
+    Field(Field),
+    Aggregate(AggregateFunction),
+}
+
+/// A single ORDER BY key together with its direction.
+#[derive(Debug, Clone)]
+pub struct OrderKey {
+    pub target: OrderTarget,
+    pub descending: bool,
+}
+
+impl FromStr for OrderKey {
+    type Err = String;
+
+    /// Parses a sort key in format: target[,asc|desc].
+    ///
+    /// The target is either a field (e.g. `size`, `name`) or an aggregate in
+    /// the format accepted by `--aggregate` (e.g. `count`, `sum,size`,
+    /// `max,modified`). Fields take precedence, so single-letter aliases such
+    /// as `c` refer to the `created` field; spell out `count` to order by it.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = s.split(',').map(str::trim).collect();
+        let descending = match parts.last().map(|part| part.to_lowercase()).as_deref() {
+            Some("desc" | "descending") => true,
+            Some("asc" | "ascending") => false,
+            _ => {
+                parts.push("asc");
+                false
+            }
+        };
+        parts.pop();
+
+        let target = parts.join(",");
+        if target.is_empty() {
+            return Err(format!("Missing field or aggregate to order by: {}", s));
+        }
+        let target = match Field::from_str(&target) {
+            Ok(field) => OrderTarget::Field(field),
+            Err(_) => match AggregateFunction::from_str(&target) {
+                Ok(AggregateFunction::Histogram(..)) => return Err(format!("Cannot order by a histogram: {}", s)),
+                Ok(aggregate) => OrderTarget::Aggregate(aggregate),
+                Err(_) => return Err(format!("Invalid order key, expected a field or aggregate: {}", s)),
+            },
+        };
+        Ok(OrderKey { target, descending })
+    }
+}
+
+impl OrderKey {
+    fn direct(&self, ordering: Ordering) -> Ordering {
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+/// Sorts files in place by the field keys in `keys`, in priority order.
+///
+/// Aggregate keys only apply to groups and are skipped here. The sort is
+/// stable, so files that compare equal on every key keep their input order.
+pub fn sort_files(files: &mut [&File], keys: &[OrderKey]) {
+    files.sort_by(|a, b| {
+        keys.iter()
+            .filter_map(|key| match &key.target {
+                OrderTarget::Field(field) => {
+                    Some(key.direct(field.value(a).compare(&field.value(b))))
+                }
+                OrderTarget::Aggregate(_) => None,
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+/// Returns the keys of `groups` in the order requested by `keys`.
+///
+/// Field keys order groups by their key only when they name the field of one
+/// of the grouping operators (`group_by`), comparing the matching part of
+/// composite keys; other field keys only affect the order of files within a
+/// group. Groups that compare equal on every key are ordered by their key, so
+/// the result is always deterministic. Key parts are compared by the rank
+/// their operator gives them, if any (see `GroupingOperator::rank`), so size
+/// buckets are ordered by size rather than by their labels.
+///
+/// # Arguments
+///
+/// * `groups` - A map from group key to a vector of file references
+/// * `keys` - The sort keys, in priority order
+/// * `group_by` - The operators the files were grouped by, outermost first
+pub fn sort_groups(groups: &HashMap<String, Vec<&File>>, keys: &[OrderKey], group_by: &[GroupingOperator]) -> Vec<String> {
+    let compare_parts = |index: usize, a: &str, b: &str| {
+        let rank = |key: &str| groups[key].first().and_then(|file| group_by[index].rank(file));
+        let part_a = key_parts(a).get(index).copied().unwrap_or("");
+        let part_b = key_parts(b).get(index).copied().unwrap_or("");
+        rank(a).cmp(&rank(b)).then_with(|| part_a.cmp(part_b))
+    };
+
+    // Aggregates are evaluated once per group rather than on every comparison
+    let values: HashMap<&String, Vec<Value>> = groups
+        .iter()
+        .map(|(name, files)| {
+            let values = keys
+                .iter()
+                .map(|key| match &key.target {
+                    OrderTarget::Aggregate(function) => function.evaluate(files),
+                    OrderTarget::Field(_) => Value::Null,
+                })
+                .collect();
+            (name, values)
+        })
+        .collect();
+
+    let mut names: Vec<&String> = groups.keys().collect();
+    names.sort_by(|a, b| {
+        keys.iter()
+            .enumerate()
+            .filter_map(|(position, key)| match &key.target {
+                OrderTarget::Field(field) => {
+                    let index = group_by.iter().position(|operator| operator.field() == *field)?;
+                    Some(key.direct(compare_parts(index, a, b)))
+                }
+                OrderTarget::Aggregate(_) => Some(key.direct(values[*a][position].compare(&values[*b][position]))),
+            })
+            .find(|ordering| ordering.is_ne())
+            .or_else(|| (0..group_by.len()).map(|index| compare_parts(index, a, b)).find(|ordering| ordering.is_ne()))
+            .unwrap_or_else(|| a.cmp(b))
+    });
+    names.into_iter().cloned().collect()
+}
+
+/// Orders multi-key groups as a hierarchy with a subtotal group for every key prefix.
+///
+/// The returned map holds the original groups plus one merged group per
+/// prefix of their composite keys (see `rollup`). The returned keys list each
+/// subtotal directly before its children, with siblings ordered by
+/// `sort_groups`. `offset` and `limit` apply to the outermost level only.
+///
+/// # Arguments
+///
+/// * `groups` - A map from composite group key to a vector of file references
+/// * `keys` - The sort keys, in priority order
+/// * `group_by` - The operators the files were grouped by, outermost first
+/// * `offset` - The number of outermost groups to skip
+/// * `limit` - The maximum number of outermost groups to keep
+pub fn nest_groups<'a>(
+    groups: &HashMap<String, Vec<&'a File>>,
+    keys: &[OrderKey],
+    group_by: &[GroupingOperator],
+    offset: usize,
+    limit: Option<usize>,
+) -> (HashMap<String, Vec<&'a File>>, Vec<String>) {
+    let levels: Vec<HashMap<String, Vec<&File>>> = (1..=group_by.len().max(1))
+        .map(|depth| rollup(groups, depth))
+        .collect();
+
+    fn visit(
+        levels: &[HashMap<String, Vec<&File>>],
+        depth: usize,
+        prefix: &str,
+        keys: &[OrderKey],
+        group_by: &[GroupingOperator],
+        ordered: &mut Vec<String>,
+    ) {
+        let children: HashMap<String, Vec<&File>> = levels[depth]
+            .iter()
+            .filter(|(key, _)| key.starts_with(&format!("{}{}", prefix, KEY_SEPARATOR)))
+            .map(|(key, files)| (key.clone(), files.clone()))
+            .collect();
+        for key in sort_groups(&children, keys, group_by) {
+            ordered.push(key.clone());
+            if depth + 1 < levels.len() {
+                visit(levels, depth + 1, &key, keys, group_by, ordered);
+            }
+        }
+    }
+
+    let mut ordered = Vec::new();
+    let top = sort_groups(&levels[0], keys, group_by);
+    for key in paginate(top, offset, limit) {
+        ordered.push(key.clone());
+        if levels.len() > 1 {
+            visit(&levels, 1, &key, keys, group_by, &mut ordered);
+        }
+    }
+
+    let merged = levels.into_iter().flatten().collect();
+    (merged, ordered)
+}
+
+/// Skips the first `offset` items and keeps at most `limit` of the rest.
+pub fn paginate<T>(items: Vec<T>, offset: usize, limit: Option<usize>) -> Vec<T> {
+    items
+        .into_iter()
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::FileType;
+    use crate::utilities::aggregate::ArithmeticAggregator;
+
+    fn mock_file(name: &str, extension: &str, size: u64) -> File {
+        File {
+            name: name.to_string(),
+            path: name.to_string(),
+            depth: 1,
+            extension: extension.to_string(),
+            size,
+            file_type: FileType::File,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sort_files_multiple_keys() {
+        let a = mock_file("a.txt", "txt", 10);
+        let b = mock_file("b.rs", "rs", 10);
+        let c = mock_file("c.txt", "txt", 30);
+        let mut files = vec![&a, &b, &c];
+        let keys = vec![
+            OrderKey { target: OrderTarget::Field(Field::Size), descending: true },
+            OrderKey { target: OrderTarget::Field(Field::Extension), descending: false },
+        ];
+        sort_files(&mut files, &keys);
+        assert_eq!(files, vec![&c, &b, &a]);
+    }
+
+    #[test]
+    fn test_sort_groups_by_aggregate() {
+        let a = mock_file("a.txt", "txt", 10);
+        let b = mock_file("b.rs", "rs", 500);
+        let c = mock_file("c.txt", "txt", 30);
+        let mut groups: HashMap<String, Vec<&File>> = HashMap::new();
+        groups.insert("txt".to_string(), vec![&a, &c]);
+        groups.insert("rs".to_string(), vec![&b]);
+
+        let by_count = vec![OrderKey { target: OrderTarget::Aggregate(AggregateFunction::Count), descending: true }];
+        assert_eq!(sort_groups(&groups, &by_count, &[GroupingOperator::Extension]), vec!["txt", "rs"]);
+
+        let by_sum = vec![OrderKey {
+            target: OrderTarget::Aggregate(AggregateFunction::Sum(ArithmeticAggregator::Size)),
+            descending: true,
+        }];
+        assert_eq!(sort_groups(&groups, &by_sum, &[GroupingOperator::Extension]), vec!["rs", "txt"]);
+    }
+
+    #[test]
+    fn test_sort_groups_by_key() {
+        let a = mock_file("a.txt", "txt", 10);
+        let b = mock_file("b.rs", "rs", 500);
+        let mut groups: HashMap<String, Vec<&File>> = HashMap::new();
+        groups.insert("txt".to_string(), vec![&a]);
+        groups.insert("rs".to_string(), vec![&b]);
+
+        assert_eq!(sort_groups(&groups, &[], &[GroupingOperator::Extension]), vec!["rs", "txt"]);
+        let descending = vec![OrderKey { target: OrderTarget::Field(Field::Extension), descending: true }];
+        assert_eq!(sort_groups(&groups, &descending, &[GroupingOperator::Extension]), vec!["txt", "rs"]);
+        // Keys on other fields do not reorder groups
+        let by_size = vec![OrderKey { target: OrderTarget::Field(Field::Size), descending: true }];
+        assert_eq!(sort_groups(&groups, &by_size, &[GroupingOperator::Extension]), vec!["rs", "txt"]);
+    }
+
+    #[test]
+    fn test_nest_groups_with_subtotals() {
+        let a = mock_file("a.txt", "txt", 10);
+        let b = mock_file("b.rs", "rs", 500);
+        let c = mock_file("c.txt", "txt", 30);
+        let d = mock_file("d.txt", "txt", 30);
+        let mut groups: HashMap<String, Vec<&File>> = HashMap::new();
+        groups.insert("txt\x0010".to_string(), vec![&a]);
+        groups.insert("txt\x0030".to_string(), vec![&c, &d]);
+        groups.insert("rs\x00500".to_string(), vec![&b]);
+
+        let by_count = vec![OrderKey { target: OrderTarget::Aggregate(AggregateFunction::Count), descending: true }];
+        let operators = [GroupingOperator::Extension, GroupingOperator::from_str("size,b").unwrap()];
+        let (merged, keys) = nest_groups(&groups, &by_count, &operators, 0, None);
+        assert_eq!(keys, vec!["txt", "txt\x0030", "txt\x0010", "rs", "rs\x00500"]);
+        assert_eq!(merged["txt"].len(), 3);
+
+        let (_, keys) = nest_groups(&groups, &by_count, &operators, 1, Some(1));
+        assert_eq!(keys, vec!["rs", "rs\x00500"]);
+    }
+
+    #[test]
+    fn test_parse_order_key() {
+        let key = OrderKey::from_str("size,desc").unwrap();
+        assert!(matches!(key.target, OrderTarget::Field(Field::Size)));
+        assert!(key.descending);
+
+        let key = OrderKey::from_str("name").unwrap();
+        assert!(matches!(key.target, OrderTarget::Field(Field::Name)));
+        assert!(!key.descending);
+
+        let key = OrderKey::from_str("sum,size,desc").unwrap();
+        assert!(matches!(key.target, OrderTarget::Aggregate(AggregateFunction::Sum(_))));
+        assert!(key.descending);
+
+        let key = OrderKey::from_str("count").unwrap();
+        assert!(matches!(key.target, OrderTarget::Aggregate(AggregateFunction::Count)));
+
+        assert!(OrderKey::from_str("desc").is_err());
+        assert!(OrderKey::from_str("colour,asc").is_err());
+        assert!(OrderKey::from_str("histogram,size,log2,desc").unwrap_err().contains("Cannot order by a histogram"));
+    }
+
+    #[test]
+    fn test_paginate() {
+        assert_eq!(paginate(vec![1, 2, 3, 4], 1, Some(2)), vec![2, 3]);
+        assert_eq!(paginate(vec![1, 2, 3], 0, None), vec![1, 2, 3]);
+        assert_eq!(paginate(vec![1, 2, 3], 5, None), Vec::<i32>::new());
+    }
+}
@@ -0,0 +1,98 @@
+/// The binary unit prefixes used by `format_size`, from kibibytes upwards.
+const IEC_UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+/// Parses a size literal such as `1500`, `10K`, `1.5MiB` or `2GB` into bytes.
+///
+/// The number may have a fraction and is followed by an optional unit
+/// (case-insensitive, optionally separated by a space):
+/// - `B` for bytes
+/// - SI units, powers of 1000: `KB`, `MB`, `GB`, `TB`, `PB`
+/// - IEC units, powers of 1024: `KiB`, `MiB`, `GiB`, `TiB`, `PiB`
+/// - Single letters, powers of 1024 like in `du` and `find`: `K`, `M`, `G`, `T`, `P`
+///
+/// Whole numbers are computed exactly; fractional results are rounded to
+/// the nearest byte.
+///
+/// # Errors
+///
+/// Returns an error if the number or the unit is invalid, or the size does not fit in 64 bits.
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid size value: {}", s.trim());
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1000,
+        "mb" => 1000u64.pow(2),
+        "gb" => 1000u64.pow(3),
+        "tb" => 1000u64.pow(4),
+        "pb" => 1000u64.pow(5),
+        "k" | "kib" => 1024,
+        "m" | "mib" => 1024u64.pow(2),
+        "g" | "gib" => 1024u64.pow(3),
+        "t" | "tib" => 1024u64.pow(4),
+        "p" | "pib" => 1024u64.pow(5),
+        _ => return Err(invalid()),
+    };
+    // Whole numbers skip the float path, which rounds integers above 2^53
+    if let Ok(number) = number.parse::<u64>() {
+        return number.checked_mul(multiplier).ok_or_else(invalid);
+    }
+    let number: f64 = number.parse().map_err(|_| invalid())?;
+    let bytes = (number * multiplier as f64).round();
+    if bytes >= u64::MAX as f64 {
+        return Err(invalid());
+    }
+    Ok(bytes as u64)
+}
+
+/// Formats a number of bytes with the largest binary unit that keeps the
+/// number at least 1, e.g. `512 B`, `1.5 KiB` or `20.0 GiB`.
+pub fn format_size(bytes: f64) -> String {
+    if bytes.abs() < 1024.0 {
+        return format!("{:.0} B", bytes);
+    }
+    let mut value = bytes / 1024.0;
+    let mut unit = 0;
+    while value.abs() >= 1024.0 && unit < IEC_UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, IEC_UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1500"), Ok(1500));
+        assert_eq!(parse_size("10K"), Ok(10 * 1024));
+        assert_eq!(parse_size("10kb"), Ok(10_000));
+        assert_eq!(parse_size("1.5MiB"), Ok(1_572_864));
+        assert_eq!(parse_size("2GB"), Ok(2_000_000_000));
+        assert_eq!(parse_size("2 gib"), Ok(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_size("0.5b"), Ok(1));
+        assert!(parse_size("").is_err());
+        assert!(parse_size("ten").is_err());
+        assert!(parse_size("10 XB").is_err());
+        assert!(parse_size("1.2.3K").is_err());
+        assert!(parse_size("100000P").is_err());
+        assert_eq!(parse_size("9007199254740993"), Ok(9_007_199_254_740_993));
+        assert_eq!(parse_size("18446744073709551615"), Ok(u64::MAX));
+        assert_eq!(parse_size("8589934591K"), Ok(8_589_934_591 * 1024));
+        assert!(parse_size("18446744073709551616").is_err());
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0.0), "0 B");
+        assert_eq!(format_size(1023.0), "1023 B");
+        assert_eq!(format_size(1536.0), "1.5 KiB");
+        assert_eq!(format_size(20.0 * 1024f64.powi(3)), "20.0 GiB");
+        assert_eq!(format_size(1024f64.powi(7)), "1024.0 EiB");
+    }
+}
@@ -0,0 +1,482 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, Days, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeDelta, TimeZone};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::utilities::filter::Comparison;
+
+static NOW: OnceLock<DateTime<Local>> = OnceLock::new();
+static ZONE: OnceLock<Zone> = OnceLock::new();
+static DATE_FORMAT: OnceLock<String> = OnceLock::new();
+
+/// The format timestamps are shown in by text tables unless `--date-format` is given.
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Returns the current time, fixed at its first use.
+///
+/// Relative literals such as `7d` and the `age` field are all measured from
+/// this instant, so every file of a run is compared against the same moment.
+pub fn now() -> DateTime<Local> {
+    *NOW.get_or_init(Local::now)
+}
+
+/// The time zone date literals are interpreted in, time groups are formed in
+/// and timestamps are shown in.
+///
+/// # Variants
+/// - `Local`: The zone of the system, as configured by `TZ` or `/etc/localtime`
+/// - `Rules(tz::TimeZone)`: UTC, a fixed offset or a named IANA zone read from
+///   the system time zone database
+#[derive(Debug, Clone, PartialEq)]
+pub enum Zone {
+    Local,
+    Rules(tz::TimeZone),
+}
+
+impl FromStr for Zone {
+    type Err = String;
+
+    /// Parses `local`, `UTC`, a fixed offset such as `+02:00`, `-0530`, `+2`
+    /// or `UTC+2`, or an IANA zone name such as `Europe/Warsaw`.
+    ///
+    /// Offsets are east of UTC, as in ISO 8601, so `UTC+2` is two hours ahead
+    /// of UTC (unlike in the POSIX `TZ` variable).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.to_lowercase().as_str() {
+            "local" => return Ok(Zone::Local),
+            "utc" | "gmt" | "z" => return Ok(Zone::Rules(tz::TimeZone::utc())),
+            _ => {}
+        }
+        if let Some(seconds) = parse_offset(s) {
+            return tz::TimeZone::fixed(seconds)
+                .map(Zone::Rules)
+                .map_err(|_| format!("Invalid UTC offset: {}", s));
+        }
+        if s.is_empty() || s.contains("..") {
+            return Err(format!("Unknown time zone: {}", s));
+        }
+        tz::TimeZone::from_posix_tz(s)
+            .map(Zone::Rules)
+            .map_err(|_| format!("Unknown time zone: {}", s))
+    }
+}
+
+impl Zone {
+    /// Converts a time to the zone, for calendar computations and display.
+    pub fn convert(&self, time: DateTime<Local>) -> DateTime<FixedOffset> {
+        time.with_timezone(&self.offset_at(time.timestamp()))
+    }
+
+    /// The UTC offset of the zone at a Unix timestamp.
+    fn offset_at(&self, timestamp: i64) -> FixedOffset {
+        match self {
+            Zone::Local => Local
+                .timestamp_opt(timestamp, 0)
+                .single()
+                .map_or(FixedOffset::east_opt(0).unwrap(), |time| time.offset().fix()),
+            Zone::Rules(rules) => rules
+                .find_local_time_type(timestamp)
+                .ok()
+                .and_then(|local_time_type| FixedOffset::east_opt(local_time_type.ut_offset()))
+                .unwrap_or(FixedOffset::east_opt(0).unwrap()),
+        }
+    }
+
+    /// Interprets a naive date and time in the zone, taking the earlier
+    /// instant when clocks are turned back.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the time does not exist in the zone, e.g. during a
+    /// daylight saving time gap.
+    pub fn resolve(&self, naive: NaiveDateTime) -> Result<DateTime<Local>, String> {
+        let invalid = || format!("Invalid local date/time: {}", naive);
+        if let Zone::Local = self {
+            return Local.from_local_datetime(&naive).earliest().ok_or_else(invalid);
+        }
+        // The offset in effect at the instant is one of those in effect a day around it
+        let guess = naive.and_utc().timestamp();
+        [guess - 86_400, guess, guess + 86_400]
+            .into_iter()
+            .map(|timestamp| self.offset_at(timestamp).local_minus_utc() as i64)
+            .map(|offset| guess - offset)
+            .filter(|timestamp| guess - self.offset_at(*timestamp).local_minus_utc() as i64 == *timestamp)
+            .min()
+            .and_then(|timestamp| Local.timestamp_opt(timestamp, 0).single())
+            .ok_or_else(invalid)
+    }
+}
+
+/// Parses a fixed UTC offset: `+02:00`, `-0530`, `+2`, optionally prefixed
+/// with `UTC` or `GMT`. Returns the offset in seconds east of UTC.
+fn parse_offset(s: &str) -> Option<i32> {
+    let prefix = s.get(..3).filter(|prefix| prefix.eq_ignore_ascii_case("utc") || prefix.eq_ignore_ascii_case("gmt"));
+    let s = &s[prefix.map_or(0, str::len)..];
+    let sign = match s.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits = s[1..].replace(':', "");
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => (digits.parse::<i32>().ok()?, 0),
+        3 | 4 => (digits[..digits.len() - 2].parse().ok()?, digits[digits.len() - 2..].parse::<i32>().ok()?),
+        _ => return None,
+    };
+    (hours < 24 && minutes < 60).then_some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Sets the zone returned by `zone`. Only the first call has an effect, and
+/// only if it happens before the zone is first used.
+pub fn set_zone(zone: Zone) {
+    let _ = ZONE.set(zone);
+}
+
+/// Returns the zone set by `--tz`, or the local zone.
+pub fn zone() -> &'static Zone {
+    ZONE.get_or_init(|| Zone::Local)
+}
+
+/// Parses a `--date-format`: a `strftime`-style format such as `%d.%m.%Y %H:%M`,
+/// or one of the names `iso` (RFC 3339 with offset), `rfc2822` and `unix` (seconds since 1970).
+///
+/// # Errors
+///
+/// Returns an error if the format contains an unknown specifier.
+pub fn parse_date_format(s: &str) -> Result<String, String> {
+    let format = match s.trim().to_lowercase().as_str() {
+        "iso" | "rfc3339" => "%Y-%m-%dT%H:%M:%S%:z",
+        "rfc2822" => "%a, %d %b %Y %H:%M:%S %z",
+        "unix" | "epoch" => "%s",
+        _ => s,
+    };
+    if format.is_empty() || StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("Invalid date format: {}", s));
+    }
+    Ok(format.to_string())
+}
+
+/// Sets the format used by `format_time`. Only the first call has an effect.
+pub fn set_date_format(format: String) {
+    let _ = DATE_FORMAT.set(format);
+}
+
+/// Formats a timestamp for text tables, in the zone set by `--tz` and the
+/// format set by `--date-format`.
+pub fn format_time(time: DateTime<Local>) -> String {
+    let format = DATE_FORMAT.get().map_or(DEFAULT_DATE_FORMAT, String::as_str);
+    zone().convert(time).format(format).to_string()
+}
+
+/// A point in time or a calendar period, as written in a condition.
+///
+/// Literals naming a whole period, such as a date (`2026-10-15`) or
+/// `this month`, cover every instant from `start` up to, but excluding,
+/// `end`. Literals naming an instant (`2026-10-15T08:30`, `7d`) have
+/// `start == end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeRange {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+}
+
+impl From<DateTime<Local>> for TimeRange {
+    fn from(instant: DateTime<Local>) -> Self {
+        TimeRange { start: instant, end: instant }
+    }
+}
+
+impl TimeRange {
+    /// Compares a time with the range.
+    ///
+    /// A time equals a period if it lies within it, and is greater than the
+    /// period if it lies after its end, so `modified = yesterday` and
+    /// `modified > yesterday` read naturally. Instants compare as usual.
+    pub fn compare(&self, time: DateTime<Local>, comparison: &Comparison) -> bool {
+        if self.start == self.end {
+            return comparison.compare(time, self.start);
+        }
+        let within = self.start <= time && time < self.end;
+        match comparison {
+            Comparison::Eq => within,
+            Comparison::Ne => !within,
+            Comparison::Gt => time >= self.end,
+            Comparison::Ge => time >= self.start,
+            Comparison::Lt => time < self.start,
+            Comparison::Le => time < self.end,
+        }
+    }
+}
+
+/// Parses a date/time literal.
+///
+/// # Accepted formats
+/// - ISO 8601 dates and datetimes: `2026-10-15`, `2026-10`, `2026-10-15T08:30`,
+///   `2026-10-15 08:30:15`, `2026-10-15T08:30:15+02:00`, `2026-10-15T06:30:15Z`
+/// - `dd.mm.yyyy hh:mm`, `dd.mm.yyyy` and `hh:mm` (today)
+/// - `now`, `today`, `yesterday`, `tomorrow`
+/// - `this week`, `this month`, `this year`, `last week`, `last month`, `last year`
+///   (weeks start on Monday)
+/// - Durations before now, with or without `ago`: `7d`, `24h`, `3 weeks ago`, `1w 2d`
+///
+/// Dates, months and named periods yield the whole period; everything else
+/// yields an instant. Times without an offset, and calendar days, are taken
+/// in the zone set by `--tz` (see `zone`).
+///
+/// # Errors
+///
+/// Returns an error if the literal matches none of the formats, or names a
+/// local time that does not exist (e.g. during a daylight saving time gap).
+pub fn parse_time(s: &str) -> Result<TimeRange, String> {
+    parse_time_in(s, zone())
+}
+
+/// Parses a date/time literal like `parse_time`, in the given zone.
+pub fn parse_time_in(s: &str, zone: &Zone) -> Result<TimeRange, String> {
+    let s = s.trim();
+    let lower = s.to_lowercase();
+    let today = zone.convert(now()).date_naive();
+    let day_range = |date: NaiveDate| period(zone, date, date + Days::new(1));
+
+    match lower.as_str() {
+        "now" => return Ok(now().into()),
+        "today" => return day_range(today),
+        "yesterday" => return day_range(today - Days::new(1)),
+        "tomorrow" => return day_range(today + Days::new(1)),
+        _ => {}
+    }
+    if let Some((which, unit)) = lower.split_once(char::is_whitespace)
+        && let Some(range) = named_period(zone, which, unit.trim(), today)
+    {
+        return range;
+    }
+
+    let upper = s.to_uppercase();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(&upper) {
+        return Ok(datetime.with_timezone(&Local).into());
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M", "%d.%m.%Y %H:%M"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(&upper, format) {
+            return zone.resolve(naive).map(TimeRange::from);
+        }
+    }
+    for format in ["%Y-%m-%d", "%d.%m.%Y"] {
+        if let Ok(date) = NaiveDate::parse_from_str(s, format) {
+            return day_range(date);
+        }
+    }
+    if let Ok(month) = NaiveDate::parse_from_str(&format!("{}-01", s), "%Y-%m-%d") {
+        return period(zone, month, month + Months::new(1));
+    }
+    if let Ok(time) = NaiveTime::parse_from_str(s, "%H:%M") {
+        return zone.resolve(today.and_time(time)).map(TimeRange::from);
+    }
+    if let Ok(duration) = parse_duration(lower.strip_suffix("ago").unwrap_or(&lower)) {
+        return Ok((now() - duration).into());
+    }
+    Err(format!("Invalid date/time format: {}", s))
+}
+
+/// Parses a duration such as `90s`, `24h`, `7 days` or `1w 2d`.
+///
+/// Each part is a whole number followed by a unit: `s`/`sec`/`seconds`,
+/// `m`/`min`/`minutes`, `h`/`hours`, `d`/`days`, `w`/`weeks`,
+/// `mo`/`months` (30 days) or `y`/`years` (365 days).
+///
+/// # Errors
+///
+/// Returns an error if the duration is empty, a number is missing or the unit is unknown.
+pub fn parse_duration(s: &str) -> Result<TimeDelta, String> {
+    let invalid = || format!("Invalid duration: {}", s.trim());
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut total = TimeDelta::zero();
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let amount: i64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = rest[digits..].trim_start();
+        let letters = rest.find(|c: char| !c.is_alphabetic()).unwrap_or(rest.len());
+        let seconds = match &rest[..letters].to_lowercase()[..] {
+            "s" | "sec" | "secs" | "second" | "seconds" => 1,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600,
+            "d" | "day" | "days" => 86_400,
+            "w" | "week" | "weeks" => 7 * 86_400,
+            "mo" | "month" | "months" => 30 * 86_400,
+            "y" | "yr" | "year" | "years" => 365 * 86_400,
+            _ => return Err(invalid()),
+        };
+        total += TimeDelta::try_seconds(amount.checked_mul(seconds).ok_or_else(invalid)?).ok_or_else(invalid)?;
+        rest = rest[letters..].trim_start_matches([' ', ',']);
+    }
+    Ok(total)
+}
+
+/// Resolves `this week`, `last month` and similar periods relative to `today`.
+fn named_period(zone: &Zone, which: &str, unit: &str, today: NaiveDate) -> Option<Result<TimeRange, String>> {
+    let back = match which {
+        "this" => 0,
+        "last" => 1,
+        _ => return None,
+    };
+    let (start, end) = match unit {
+        "week" => {
+            let monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+            let start = monday - Days::new(7 * back);
+            (start, start + Days::new(7))
+        }
+        "month" => {
+            let start = today.with_day(1)? - Months::new(back as u32);
+            (start, start + Months::new(1))
+        }
+        "year" => {
+            let start = NaiveDate::from_ymd_opt(today.year() - back as i32, 1, 1)?;
+            (start, start + Months::new(12))
+        }
+        _ => return None,
+    };
+    Some(period(zone, start, end))
+}
+
+/// The time range from the start of one day up to the start of another, in a zone.
+fn period(zone: &Zone, start: NaiveDate, end: NaiveDate) -> Result<TimeRange, String> {
+    Ok(TimeRange {
+        start: zone.resolve(start.and_time(NaiveTime::MIN))?,
+        end: zone.resolve(end.and_time(NaiveTime::MIN))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local_time(s: &str) -> DateTime<Local> {
+        Zone::Local.resolve(NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_absolute_times() {
+        let instant = parse_time("2026-10-15T08:30").unwrap();
+        assert_eq!(instant, local_time("2026-10-15 08:30:00").into());
+        assert_eq!(parse_time("2026-10-15 08:30:15").unwrap(), local_time("2026-10-15 08:30:15").into());
+        assert_eq!(parse_time("15.10.2026 08:30").unwrap(), instant);
+        // Literals are lowercased by the WHERE parser
+        assert_eq!(parse_time("2026-10-15t08:30").unwrap(), instant);
+        let utc = parse_time("2026-10-15T06:30:00Z").unwrap();
+        assert_eq!(utc.start.timestamp(), 1_792_045_800);
+
+        let day = parse_time("2026-10-15").unwrap();
+        assert_eq!(day.start, local_time("2026-10-15 00:00:00"));
+        assert_eq!(day.end, local_time("2026-10-16 00:00:00"));
+        assert_eq!(parse_time("15.10.2026").unwrap(), day);
+        let month = parse_time("2026-02").unwrap();
+        assert_eq!(month.end, local_time("2026-03-01 00:00:00"));
+
+        assert!(parse_time("15/10/2026").is_err());
+        assert!(parse_time("next tuesday").is_err());
+    }
+
+    #[test]
+    fn test_parse_relative_times() {
+        let today = parse_time("today").unwrap();
+        assert_eq!(today.start, Zone::Local.resolve(now().date_naive().and_time(NaiveTime::MIN)).unwrap());
+        assert_eq!(parse_time("yesterday").unwrap().end, today.start);
+        assert_eq!(parse_time("7d").unwrap(), (now() - TimeDelta::days(7)).into());
+        assert_eq!(parse_time("3 weeks ago").unwrap(), (now() - TimeDelta::weeks(3)).into());
+        assert_eq!(parse_time("1w 2d").unwrap(), (now() - TimeDelta::days(9)).into());
+
+        let this_month = parse_time("this month").unwrap();
+        assert_eq!(this_month.start.day(), 1);
+        assert!(this_month.compare(now(), &Comparison::Eq));
+        assert_eq!(parse_time("last month").unwrap().end, this_month.start);
+        let this_week = parse_time("this week").unwrap();
+        assert_eq!(this_week.start.weekday(), chrono::Weekday::Mon);
+        assert_eq!(this_week.end - this_week.start, TimeDelta::weeks(1));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("24h"), Ok(TimeDelta::hours(24)));
+        assert_eq!(parse_duration("90 min"), Ok(TimeDelta::minutes(90)));
+        assert_eq!(parse_duration("1h30m"), Ok(TimeDelta::minutes(90)));
+        assert_eq!(parse_duration("2 weeks, 1 day"), Ok(TimeDelta::days(15)));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("3 fortnights").is_err());
+    }
+
+    #[test]
+    fn test_compare_with_range() {
+        let day = parse_time("2026-10-15").unwrap();
+        let noon = local_time("2026-10-15 12:00:00");
+        let next_day = local_time("2026-10-16 00:00:00");
+        assert!(day.compare(noon, &Comparison::Eq));
+        assert!(!day.compare(next_day, &Comparison::Eq));
+        assert!(day.compare(next_day, &Comparison::Gt));
+        assert!(!day.compare(noon, &Comparison::Gt));
+        assert!(day.compare(noon, &Comparison::Ge));
+        assert!(day.compare(noon, &Comparison::Le));
+        assert!(!day.compare(noon, &Comparison::Lt));
+        assert!(day.compare(next_day, &Comparison::Ne));
+    }
+
+    #[test]
+    fn test_parse_zones() {
+        assert_eq!(Zone::from_str("local"), Ok(Zone::Local));
+        assert_eq!(Zone::from_str("UTC"), Ok(Zone::Rules(tz::TimeZone::utc())));
+        let plus_two = Zone::Rules(tz::TimeZone::fixed(7200).unwrap());
+        assert_eq!(Zone::from_str("+02:00").as_ref(), Ok(&plus_two));
+        assert_eq!(Zone::from_str("UTC+2").as_ref(), Ok(&plus_two));
+        assert_eq!(Zone::from_str("+0200").as_ref(), Ok(&plus_two));
+        assert_eq!(Zone::from_str("-05:30"), Ok(Zone::Rules(tz::TimeZone::fixed(-19_800).unwrap())));
+        assert!(Zone::from_str("+25:00").is_err());
+        assert!(Zone::from_str("Mars/Olympus_Mons").is_err());
+        assert!(Zone::from_str("../../etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_parse_time_in_zones() {
+        let utc = Zone::from_str("UTC").unwrap();
+        let plus_two = Zone::from_str("+02:00").unwrap();
+        let day = parse_time_in("2026-10-15", &utc).unwrap();
+        assert_eq!(day.start.timestamp(), 1_792_022_400);
+        assert_eq!(day.end.timestamp(), 1_792_022_400 + 86_400);
+        assert_eq!(parse_time_in("2026-10-15", &plus_two).unwrap().start.timestamp(), 1_792_022_400 - 7200);
+        assert_eq!(parse_time_in("2026-10-15 08:30", &plus_two).unwrap().start.timestamp(), 1_792_045_800);
+        // Explicit offsets win over the zone
+        assert_eq!(parse_time_in("2026-10-15T06:30:00Z", &plus_two).unwrap().start.timestamp(), 1_792_045_800);
+        assert_eq!(plus_two.convert(day.start).to_rfc3339(), "2026-10-15T02:00:00+02:00");
+    }
+
+    #[test]
+    fn test_named_zone_rules() {
+        // Needs the system time zone database
+        let Ok(warsaw) = Zone::from_str("Europe/Warsaw") else {
+            return;
+        };
+        let summer = parse_time_in("2026-07-01", &warsaw).unwrap();
+        assert_eq!(warsaw.convert(summer.start).offset().local_minus_utc(), 7200);
+        let winter = parse_time_in("2026-12-01", &warsaw).unwrap();
+        assert_eq!(warsaw.convert(winter.start).offset().local_minus_utc(), 3600);
+        // Clocks jump from 02:00 to 03:00 on the last Sunday of March
+        assert!(parse_time_in("2026-03-29 02:30", &warsaw).is_err());
+        // 02:30 happens twice on the last Sunday of October; the earlier one is taken
+        let ambiguous = parse_time_in("2026-10-25 02:30", &warsaw).unwrap();
+        assert_eq!(warsaw.convert(ambiguous.start).offset().local_minus_utc(), 7200);
+    }
+
+    #[test]
+    fn test_parse_date_format() {
+        assert_eq!(parse_date_format("%d.%m.%Y"), Ok("%d.%m.%Y".to_string()));
+        assert_eq!(parse_date_format("iso"), Ok("%Y-%m-%dT%H:%M:%S%:z".to_string()));
+        assert!(parse_date_format("%Q").is_err());
+        assert!(parse_date_format("").is_err());
+    }
+}
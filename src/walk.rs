@@ -0,0 +1,195 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::file::File;
+
+/// Controls how far `walk` descends into a directory tree.
+///
+/// Depth is counted from the root directory: entries directly inside the
+/// root have depth 1, entries of their subdirectories depth 2, and so on.
+///
+/// # Fields
+/// - `min_depth`: Entries shallower than this depth are traversed but not returned
+/// - `max_depth`: Entries deeper than this depth are neither traversed nor returned
+///   (`None` means unlimited)
+#[derive(Debug, Clone)]
+pub struct WalkOptions {
+    pub min_depth: usize,
+    pub max_depth: Option<usize>,
+}
+
+impl Default for WalkOptions {
+    /// Lists only the immediate children of the root, like a plain `ls`.
+    fn default() -> Self {
+        Self {
+            min_depth: 1,
+            max_depth: Some(1),
+        }
+    }
+}
+
+/// Reads every entry below `root` that lies within the configured depth range.
+///
+/// Subdirectories are visited depth-first. Symbolic links to directories are
+/// reported as entries but never followed, so cycles cannot occur. Entries
+/// that cannot be read or whose metadata cannot be read, and subdirectories
+/// that cannot be opened, are reported on stderr and skipped.
+///
+/// # Arguments
+///
+/// * `root` - The directory to start from
+/// * `options` - The depth limits to apply
+///
+/// # Errors
+///
+/// Returns an error if `root` itself cannot be read.
+pub fn walk(root: &Path, options: &WalkOptions) -> std::io::Result<Vec<File>> {
+    let mut files = Vec::new();
+    let mut pending = vec![(root.to_path_buf(), 1)];
+    let mut is_root = true;
+
+    while let Some((dir, depth)) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if is_root => return Err(e),
+            Err(e) => {
+                eprintln!("Warning: Could not read directory {:?}: {}", dir, e);
+                continue;
+            }
+        };
+        is_root = false;
+        visit(entries, &dir, root, depth, options, &mut files, &mut pending);
+    }
+
+    Ok(files)
+}
+
+/// Collects the entries of one directory at `depth` into `files`, queueing
+/// subdirectories to descend into on `pending`.
+fn visit(
+    entries: impl Iterator<Item = std::io::Result<fs::DirEntry>>,
+    dir: &Path,
+    root: &Path,
+    depth: usize,
+    options: &WalkOptions,
+    files: &mut Vec<File>,
+    pending: &mut Vec<(PathBuf, usize)>,
+) {
+    if options.max_depth.is_some_and(|max| depth > max) {
+        return;
+    }
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                eprintln!("Warning: Could not read an entry of directory {:?}: {}", dir, e);
+                continue;
+            }
+        };
+        let descend = options.max_depth.is_none_or(|max| depth < max)
+            && entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+        if descend {
+            pending.push((entry.path(), depth + 1));
+        }
+
+        if depth < options.min_depth {
+            continue;
+        }
+        match File::from_dir_entry(&entry, root, depth) {
+            Ok(file) => files.push(file),
+            Err(e) => eprintln!("Warning: Could not read file {:?}: {}", entry.path(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn sample_tree(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("lsdir-walk-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::write(root.join("top.txt"), "top").unwrap();
+        fs::write(root.join("a/mid.txt"), "mid").unwrap();
+        fs::write(root.join("a/b/deep.txt"), "deep").unwrap();
+        root
+    }
+
+    fn paths(files: &[File]) -> Vec<String> {
+        let mut paths: Vec<String> = files.iter().map(|file| file.path.clone()).collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_default_lists_single_level() {
+        let root = sample_tree("default");
+        let files = walk(&root, &WalkOptions::default()).unwrap();
+        assert_eq!(paths(&files), vec!["a", "top.txt"]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_unlimited_depth() {
+        let root = sample_tree("unlimited");
+        let options = WalkOptions { min_depth: 1, max_depth: None };
+        let files = walk(&root, &options).unwrap();
+        assert_eq!(paths(&files), vec!["a", "a/b", "a/b/deep.txt", "a/mid.txt", "top.txt"]);
+        let deep = files.iter().find(|file| file.name == "deep.txt").unwrap();
+        assert_eq!(deep.depth, 3);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_unreadable_entries_are_skipped() {
+        let root = sample_tree("entries");
+        let entries = std::iter::once(Err(std::io::Error::other("entry vanished"))).chain(fs::read_dir(&root).unwrap());
+        let (mut files, mut pending) = (Vec::new(), Vec::new());
+        visit(entries, &root, &root, 1, &WalkOptions::default(), &mut files, &mut pending);
+        assert_eq!(paths(&files), vec!["a", "top.txt"]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_depth_range() {
+        let root = sample_tree("range");
+        let options = WalkOptions { min_depth: 2, max_depth: Some(2) };
+        let files = walk(&root, &options).unwrap();
+        assert_eq!(paths(&files), vec!["a/b", "a/mid.txt"]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_are_not_followed() {
+        use crate::file::FileType;
+        use std::os::unix::fs::symlink;
+
+        let root = sample_tree("symlinks");
+        symlink("top.txt", root.join("good")).unwrap();
+        symlink("missing.txt", root.join("dangling")).unwrap();
+        symlink("a", root.join("dir-link")).unwrap();
+        let options = WalkOptions { min_depth: 1, max_depth: None };
+        let files = walk(&root, &options).unwrap();
+        assert!(!paths(&files).iter().any(|path| path.starts_with("dir-link/")));
+
+        let good = files.iter().find(|file| file.name == "good").unwrap();
+        assert_eq!(good.file_type, FileType::Symlink);
+        assert_eq!(good.link_target.as_deref(), Some("top.txt"));
+        assert!(!good.broken_link);
+        let dangling = files.iter().find(|file| file.name == "dangling").unwrap();
+        assert_eq!(dangling.file_type, FileType::Symlink);
+        assert!(dangling.broken_link);
+        let dir_link = files.iter().find(|file| file.name == "dir-link").unwrap();
+        assert_eq!(dir_link.file_type, FileType::Symlink);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_missing_root_is_error() {
+        let root = std::env::temp_dir().join("lsdir-walk-does-not-exist");
+        assert!(walk(&root, &WalkOptions::default()).is_err());
+    }
+}